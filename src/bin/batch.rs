@@ -0,0 +1,138 @@
+use drawmymap2_lib::components::MapAlbum;
+use drawmymap2_lib::evacuation::batch::*;
+use drawmymap2_lib::evacuation::things::*;
+
+//蒙特卡洛批量模拟：
+//cargo run --bin batch -- <album> <parameters> <runs> [--seed N] [--dt S] [--max-time S] [--out NAME]
+//结果写入./assets/results/NAME_runs.csv、NAME_summary.csv和NAME.json
+//参数扫描：再加上一个或多个 --sweep <field>=<v1,v2,...|start:end:step>，
+//每个参数组合跑runs次，结果写入NAME_sweep.csv和NAME_sweep.json
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 4 {
+        exit_with("missing arguments");
+    }
+    let album = args[1].clone();
+    let parameters = args[2].clone();
+    let runs = match args[3].parse() {
+        Ok(runs) if runs > 0 => runs,
+        _ => exit_with(&format!("runs must be a positive integer, got {}", args[3])),
+    };
+    let default = BatchConfig::default();
+    let config = BatchConfig {
+        runs,
+        seed: seed_from_args()
+            .unwrap_or_else(|e| exit_with(&e))
+            .unwrap_or(default.seed),
+        time_step: positive_from_args("--dt").unwrap_or(default.time_step),
+        max_time: positive_from_args("--max-time").unwrap_or(default.max_time),
+    };
+    let name: String = value_from_args("--out")
+        .unwrap_or_else(|e| exit_with(&e))
+        .unwrap_or(format!("{}_{}", album, parameters));
+    let sweeps = values_from_args("--sweep").unwrap_or_else(|e| exit_with(&e));
+
+    let map_album = match MapAlbum::load(&album) {
+        Ok(map_album) => map_album,
+        Err(e) => {
+            println!("album error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let para = NeededParameters::load(&parameters);
+
+    let start = std::time::Instant::now();
+    if !sweeps.is_empty() {
+        let axes = match sweeps
+            .iter()
+            .map(|spec| SweepAxis::parse(spec))
+            .collect::<Result<Vec<_>, String>>()
+        {
+            Ok(axes) => axes,
+            Err(e) => exit_with(&format!("sweep error: {}", e)),
+        };
+        let rows = match run_sweep(&map_album, &para, &axes, &config) {
+            Ok(rows) => rows,
+            Err(e) => {
+                println!("sweep error: {}", e);
+                std::process::exit(1);
+            }
+        };
+        for row in rows.iter() {
+            let point: Vec<String> = row
+                .point
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect();
+            println!(
+                "{}: dead {:.1}, evacuated {:.1}, evacuation time {:.1}s",
+                point.join(" "),
+                row.summary.dead.mean,
+                row.summary.evacuated.mean,
+                row.summary.evacuation_time.mean,
+            );
+        }
+        println!(
+            "{} combinations in {:.1}s",
+            rows.len(),
+            start.elapsed().as_secs_f32()
+        );
+        save_sweep_csv(&axes, &rows, &format!("{}_sweep", name));
+        save_sweep_json(
+            &SweepReport {
+                album,
+                parameters: para,
+                config,
+                axes,
+                rows,
+            },
+            &format!("{}_sweep", name),
+        );
+        return;
+    }
+
+    let results = run_batch(&map_album, &para, &config);
+    let summary = Summary::from_results(&results);
+    println!(
+        "{} runs in {:.1}s: dead {:.1} (p95 {}), evacuated {:.1}, evacuation time {:.1}s (p95 {:.1}s)",
+        summary.runs,
+        start.elapsed().as_secs_f32(),
+        summary.dead.mean,
+        summary.dead.p95,
+        summary.evacuated.mean,
+        summary.evacuation_time.mean,
+        summary.evacuation_time.p95,
+    );
+
+    save_results_csv(&results, &format!("{}_runs", name));
+    save_summary_csv(&summary, &format!("{}_summary", name));
+    save_report_json(
+        &BatchReport {
+            album,
+            parameters: para,
+            config,
+            summary,
+            runs: results,
+        },
+        &name,
+    );
+}
+
+//读取时间步长这类必须为正数的参数
+fn positive_from_args(name: &str) -> Option<f32> {
+    let value = value_from_args::<f32>(name).unwrap_or_else(|e| exit_with(&e))?;
+    if value > 0.0 {
+        Some(value)
+    } else {
+        exit_with(&format!("{} must be positive, got {}", name, value))
+    }
+}
+
+const USAGE: &str = "usage: batch <album> <parameters> <runs> [--seed N] [--dt S] [--max-time S] [--out NAME] [--sweep FIELD=VALUES]...";
+
+//参数有误时打印错误和用法，以非零状态退出
+fn exit_with(error: &str) -> ! {
+    println!("{}", error);
+    println!("{}", USAGE);
+    std::process::exit(1);
+}
@@ -2,7 +2,7 @@ use bevy::prelude::*;
 
 use serde::{Deserialize, Serialize};
 use serde_json;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
 use super::tile::TileType;
 
@@ -70,11 +70,189 @@ impl PartialOrd for MapMarker {
         Some(self.cmp(other))
     }
 }
+
+//按名字排序的楼层标记，HashMap的遍历顺序每次运行都不同，需要可复现的顺序时使用
+pub fn sorted_markers<T>(maps: &HashMap<MapMarker, T>) -> Vec<MapMarker> {
+    let mut markers: Vec<MapMarker> = maps.keys().cloned().collect();
+    markers.sort();
+    markers
+}
+
+//楼梯的一端：某一层上的一个楼梯格
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StairEnd {
+    pub storey: MapMarker,
+    pub tile: (usize, usize),
+}
+
+//楼梯连接，把upper层的楼梯格与lower层的楼梯格配对，疏散时人员从upper走到lower
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StairLink {
+    pub upper: StairEnd,
+    pub lower: StairEnd,
+}
+
+//电梯井：一部电梯在各层的停靠格，停靠格为电梯格
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ElevatorShaft {
+    pub name: String,
+    pub stops: Vec<StairEnd>,
+}
+
+//门的初始状态：常开、关闭（有人推开后保持打开）、自闭（有人通过后过一会儿自动关上）、锁闭（不可通行）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DoorMode {
+    #[default]
+    Open,
+    Closed,
+    SelfClosing,
+    Locked,
+}
+
+//门的设置，没有写在地图集里的门格按常开的普通门处理
+//fire_door为true的门在警报响起后自动关闭，flow为通行能力（人/秒），不填时取参数中的door_flow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DoorSpec {
+    pub storey: MapMarker,
+    pub tile: (usize, usize),
+    #[serde(default)]
+    pub mode: DoorMode,
+    #[serde(default)]
+    pub fire_door: bool,
+    #[serde(default)]
+    pub flow: Option<f32>,
+}
+
+//出口的设置，main为true的是主出口（访客只知道主出口）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExitSpec {
+    pub storey: MapMarker,
+    pub tile: (usize, usize),
+    #[serde(default)]
+    pub main: bool,
+}
+
+//一层或一个房间（rows、cols为闭区间）里各类人员的比例，键为人员类型配置中的名字
+//同一格同时落在房间和楼层的设置里时按房间的比例
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OccupantMix {
+    pub storey: MapMarker,
+    #[serde(default)]
+    pub room: Option<((usize, usize), (usize, usize))>,
+    pub mix: BTreeMap<String, f32>,
+}
+
+impl OccupantMix {
+    pub fn contains(&self, storey: &MapMarker, tile: (usize, usize)) -> bool {
+        &self.storey == storey
+            && self.room.is_none_or(|((i0, j0), (i1, j1))| {
+                (i0..=i1).contains(&tile.0) && (j0..=j1).contains(&tile.1)
+            })
+    }
+}
+
+//防烟分区：一层中的一块矩形区域（area为左上、右下两角的格，闭区间），
+//refuges为区域内的避难格，不填时取区域内的安全区格，仍没有时整个区域都可以避难
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompartmentSpec {
+    pub storey: MapMarker,
+    pub name: String,
+    pub area: ((usize, usize), (usize, usize)),
+    #[serde(default)]
+    pub refuges: Vec<(usize, usize)>,
+}
+
+impl CompartmentSpec {
+    pub fn contains(&self, tile: (usize, usize)) -> bool {
+        let ((i0, j0), (i1, j1)) = self.area;
+        (i0..=i1).contains(&tile.0) && (j0..=j1).contains(&tile.1)
+    }
+}
+
+//排烟口：一层上开着的窗或排烟口所在的格，可以画在外墙上，烟雾到这里就排出楼外
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VentSpec {
+    pub storey: MapMarker,
+    pub tile: (usize, usize),
+}
+
+//竖井或中庭：烟雾可以沿它在各层的开口之间上升，rate不填时取参数中的shaft_smoke_rate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShaftSpec {
+    pub name: String,
+    pub openings: Vec<StairEnd>,
+    #[serde(default)]
+    pub rate: Option<f32>,
+}
+
+//楼层信息：index从下往上递增，elevation为楼面标高（米），height为层高（米），
+//ground为true的楼层上的出口通向室外
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoreyInfo {
+    pub index: i32,
+    pub elevation: f32,
+    pub height: f32,
+    pub ground: bool,
+}
+
+impl StoreyInfo {
+    //没有楼层信息时按名字顺序从下往上排列，最下面一层为首层
+    pub fn from_order(index: i32) -> Self {
+        StoreyInfo {
+            index,
+            elevation: index as f32 * 3.0,
+            height: 3.0,
+            ground: index == 0,
+        }
+    }
+}
+
+//地图集文件的格式，兼容只保存了地图的旧格式
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum AlbumFile {
+    Full(Box<AlbumData>),
+    Legacy(HashMap<String, Map>),
+}
+
+//完整格式的地图集文件内容
+#[derive(Serialize, Deserialize)]
+struct AlbumData {
+    maps: HashMap<String, Map>,
+    #[serde(default)]
+    stairs: Vec<StairLink>,
+    #[serde(default)]
+    storeys: HashMap<String, StoreyInfo>,
+    #[serde(default)]
+    elevators: Vec<ElevatorShaft>,
+    #[serde(default)]
+    doors: Vec<DoorSpec>,
+    #[serde(default)]
+    exits: Vec<ExitSpec>,
+    #[serde(default)]
+    occupants: Vec<OccupantMix>,
+    #[serde(default)]
+    compartments: Vec<CompartmentSpec>,
+    #[serde(default)]
+    vents: Vec<VentSpec>,
+    #[serde(default)]
+    shafts: Vec<ShaftSpec>,
+}
+
 //地图数据的合集，用于多个地图的显示或者切换，同时承担了绘制地图过程中改变色块消息的传递
 #[derive(Resource, Debug, Clone, Serialize, Deserialize)]
 pub struct MapAlbum {
     pub maps: HashMap<MapMarker, Map>,
     pub now_map: MapMarker,
+    pub stairs: Vec<StairLink>,
+    pub storeys: HashMap<MapMarker, StoreyInfo>,
+    pub elevators: Vec<ElevatorShaft>,
+    pub doors: Vec<DoorSpec>,
+    pub exits: Vec<ExitSpec>,
+    pub occupants: Vec<OccupantMix>,
+    pub compartments: Vec<CompartmentSpec>,
+    pub vents: Vec<VentSpec>,
+    pub shafts: Vec<ShaftSpec>,
 }
 
 impl MapAlbum {
@@ -84,6 +262,15 @@ impl MapAlbum {
             now_map: MapMarker {
                 name: "default".to_string(),
             },
+            stairs: Vec::new(),
+            storeys: HashMap::new(),
+            elevators: Vec::new(),
+            doors: Vec::new(),
+            exits: Vec::new(),
+            occupants: Vec::new(),
+            compartments: Vec::new(),
+            vents: Vec::new(),
+            shafts: Vec::new(),
         }
     }
 
@@ -103,17 +290,448 @@ impl MapAlbum {
     pub fn save(&self, name: &str) {
         let path = format!("./assets/mapalbums/albums/{}.json", name);
         let file = std::fs::File::create(path).unwrap();
-        let value = self.into_string_map();
+        let mut map_album = self.clone();
+        map_album.fill_storeys();
+        let value = AlbumFile::Full(Box::new(AlbumData {
+            maps: map_album.into_string_map(),
+            stairs: map_album.stairs.clone(),
+            storeys: map_album
+                .storeys
+                .iter()
+                .map(|(marker, info)| (marker.name.clone(), info.clone()))
+                .collect(),
+            elevators: map_album.elevators.clone(),
+            doors: map_album.doors.clone(),
+            exits: map_album.exits.clone(),
+            occupants: map_album.occupants.clone(),
+            compartments: map_album.compartments.clone(),
+            vents: map_album.vents.clone(),
+            shafts: map_album.shafts.clone(),
+        }));
         serde_json::to_writer(std::io::BufWriter::new(file), &value).unwrap();
     }
 
-    pub fn load(name: &str) -> Self {
+    //读取地图集并检查楼梯、电梯、门等设置，有错误时返回所有错误
+    pub fn load(name: &str) -> Result<Self, String> {
         let path = format!("./assets/mapalbums/albums/{}.json", name);
         println!("{}", path);
-        let file = std::fs::File::open(path).unwrap();
-        let value: HashMap<String, Map> =
-            serde_json::from_reader(std::io::BufReader::new(file)).unwrap();
-        MapAlbum::from_string_map(value)
+        let file = std::fs::File::open(&path).map_err(|e| format!("{}: {}", path, e))?;
+        let value: AlbumFile = serde_json::from_reader(std::io::BufReader::new(file))
+            .map_err(|e| format!("{}: {}", path, e))?;
+        let map_album = match value {
+            AlbumFile::Full(data) => {
+                let data = *data;
+                let mut map_album = MapAlbum::from_string_map(data.maps);
+                map_album.stairs = data.stairs;
+                map_album.elevators = data.elevators;
+                map_album.doors = data.doors;
+                map_album.exits = data.exits;
+                map_album.occupants = data.occupants;
+                map_album.compartments = data.compartments;
+                map_album.vents = data.vents;
+                map_album.shafts = data.shafts;
+                for (name, info) in data.storeys {
+                    map_album.storeys.insert(MapMarker { name }, info);
+                }
+                map_album.fill_storeys();
+                map_album
+            }
+            AlbumFile::Legacy(maps) => MapAlbum::from_legacy(maps),
+        };
+        let errors = map_album.check();
+        if errors.is_empty() {
+            Ok(map_album)
+        } else {
+            Err(format!("{}:\n{}", path, errors.join("\n")))
+        }
+    }
+
+    //旧格式只保存了地图：楼层按名字顺序从下往上，上下相邻两层同一位置上的楼梯格相连
+    pub fn from_legacy(maps: HashMap<String, Map>) -> Self {
+        let mut map_album = MapAlbum::from_string_map(maps);
+        map_album.fill_storeys();
+        map_album.pair_stairs();
+        map_album
+    }
+
+    //把上下相邻两层同一位置上的楼梯格连起来
+    pub fn pair_stairs(&mut self) {
+        let markers = self.ordered_markers();
+        for pair in markers.windows(2) {
+            let lower = &self.maps[&pair[0]];
+            let upper = &self.maps[&pair[1]];
+            for (i, row) in lower.tiles.iter().enumerate() {
+                for (j, tile) in row.iter().enumerate() {
+                    if *tile == TileType::Stair
+                        && upper.tiles.get(i).and_then(|row| row.get(j)) == Some(&TileType::Stair)
+                    {
+                        self.stairs.push(StairLink {
+                            upper: StairEnd {
+                                storey: pair[1].clone(),
+                                tile: (i, j),
+                            },
+                            lower: StairEnd {
+                                storey: pair[0].clone(),
+                                tile: (i, j),
+                            },
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    //汇总所有检查的错误
+    pub fn check(&self) -> Vec<String> {
+        [
+            ("stair", self.check_stairs()),
+            ("elevator", self.check_elevators()),
+            ("door", self.check_doors()),
+            ("exit", self.check_exits()),
+            ("compartment", self.check_compartments()),
+            ("vent", self.check_vents()),
+            ("shaft", self.check_shafts()),
+        ]
+        .into_iter()
+        .flat_map(|(kind, errors)| {
+            errors
+                .into_iter()
+                .map(move |error| format!("{} error: {}", kind, error))
+        })
+        .collect()
+    }
+
+    //为没有楼层信息的地图补上信息：排在已有楼层之上，按名字顺序从下往上
+    //一个楼层信息都没有时，名字最靠前的一层为首层
+    pub fn fill_storeys(&mut self) {
+        let mut next = self
+            .storeys
+            .values()
+            .map(|x| x.index + 1)
+            .max()
+            .unwrap_or(0);
+        for marker in sorted_markers(&self.maps) {
+            if let std::collections::hash_map::Entry::Vacant(entry) = self.storeys.entry(marker) {
+                entry.insert(StoreyInfo::from_order(next));
+                next += 1;
+            }
+        }
+    }
+
+    pub fn storey_info(&self, storey: &MapMarker) -> StoreyInfo {
+        match self.storeys.get(storey) {
+            Some(info) => info.clone(),
+            None => StoreyInfo::from_order(self.storeys.len() as i32),
+        }
+    }
+
+    pub fn is_ground(&self, storey: &MapMarker) -> bool {
+        self.storey_info(storey).ground
+    }
+
+    //按楼层从下往上排列的地图标记
+    pub fn ordered_markers(&self) -> Vec<MapMarker> {
+        let mut markers = sorted_markers(&self.maps);
+        markers.sort_by_key(|marker| self.storey_info(marker).index);
+        markers
+    }
+
+    //离最近的首层隔了几层
+    pub fn ground_distance(&self, storey: &MapMarker) -> i32 {
+        let index = self.storey_info(storey).index;
+        self.storeys
+            .values()
+            .filter(|info| info.ground)
+            .map(|info| (info.index - index).abs())
+            .min()
+            .unwrap_or(0)
+    }
+
+    //在首层以下（地下室），疏散时要上楼
+    pub fn is_below_ground(&self, storey: &MapMarker) -> bool {
+        let index = self.storey_info(storey).index;
+        self.storeys
+            .values()
+            .filter(|info| info.ground)
+            .map(|info| info.index)
+            .min()
+            .is_some_and(|ground| index < ground)
+    }
+
+    //检查楼梯连接的两端是否都在存在的楼层的楼梯格上，且upper确实在lower之上
+    pub fn check_stairs(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for link in self.stairs.iter() {
+            if self.storey_info(&link.upper.storey).index
+                <= self.storey_info(&link.lower.storey).index
+            {
+                errors.push(format!(
+                    "{} is not above {}",
+                    link.upper.storey.name, link.lower.storey.name
+                ));
+            }
+            for end in [&link.upper, &link.lower] {
+                let (i, j) = end.tile;
+                match self.maps.get(&end.storey) {
+                    None => errors.push(format!("no storey named {}", end.storey.name)),
+                    Some(map) => {
+                        if i >= map.tiles.len() || j >= map.tiles[0].len() {
+                            errors
+                                .push(format!("{} ({}, {}) is out of map", end.storey.name, i, j));
+                        } else if map.tiles[i][j] != TileType::Stair {
+                            errors
+                                .push(format!("{} ({}, {}) is not a stair", end.storey.name, i, j));
+                        }
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    //检查电梯的停靠格是否都在存在的楼层的电梯格上，每层最多停靠一次
+    pub fn check_elevators(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for shaft in self.elevators.iter() {
+            for (n, stop) in shaft.stops.iter().enumerate() {
+                let (i, j) = stop.tile;
+                if shaft.stops[..n].iter().any(|x| x.storey == stop.storey) {
+                    errors.push(format!(
+                        "{} stops twice at {}",
+                        shaft.name, stop.storey.name
+                    ));
+                }
+                match self.maps.get(&stop.storey) {
+                    None => errors.push(format!("no storey named {}", stop.storey.name)),
+                    Some(map) => {
+                        if i >= map.tiles.len() || j >= map.tiles[0].len() {
+                            errors
+                                .push(format!("{} ({}, {}) is out of map", stop.storey.name, i, j));
+                        } else if map.tiles[i][j] != TileType::Elevator {
+                            errors.push(format!(
+                                "{} ({}, {}) is not an elevator",
+                                stop.storey.name, i, j
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    //检查门的设置是否都在存在的楼层的门格上
+    pub fn check_doors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for door in self.doors.iter() {
+            let (i, j) = door.tile;
+            match self.maps.get(&door.storey) {
+                None => errors.push(format!("no storey named {}", door.storey.name)),
+                Some(map) => {
+                    if i >= map.tiles.len() || j >= map.tiles[0].len() {
+                        errors.push(format!("{} ({}, {}) is out of map", door.storey.name, i, j));
+                    } else if map.tiles[i][j] != TileType::Door {
+                        errors.push(format!("{} ({}, {}) is not a door", door.storey.name, i, j));
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    //检查出口的设置是否都在存在的楼层的出口格上
+    pub fn check_exits(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for exit in self.exits.iter() {
+            let (i, j) = exit.tile;
+            match self.maps.get(&exit.storey) {
+                None => errors.push(format!("no storey named {}", exit.storey.name)),
+                Some(map) => {
+                    if i >= map.tiles.len() || j >= map.tiles[0].len() {
+                        errors.push(format!("{} ({}, {}) is out of map", exit.storey.name, i, j));
+                    } else if map.tiles[i][j] != TileType::Exit {
+                        errors.push(format!(
+                            "{} ({}, {}) is not an exit",
+                            exit.storey.name, i, j
+                        ));
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    //某格适用的人员比例：先找包含它的房间，再找它所在的楼层
+    pub fn occupant_mix(
+        &self,
+        storey: &MapMarker,
+        tile: (usize, usize),
+    ) -> Option<&BTreeMap<String, f32>> {
+        let mixes: Vec<&OccupantMix> = self
+            .occupants
+            .iter()
+            .filter(|x| x.contains(storey, tile))
+            .collect();
+        mixes
+            .iter()
+            .find(|x| x.room.is_some())
+            .or(mixes.first())
+            .map(|x| &x.mix)
+    }
+
+    //检查防烟分区是否在存在的楼层内，避难格是否在分区内且可以站人
+    pub fn check_compartments(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for compartment in self.compartments.iter() {
+            let Some(map) = self.maps.get(&compartment.storey) else {
+                errors.push(format!("no storey named {}", compartment.storey.name));
+                continue;
+            };
+            let ((i0, j0), (i1, j1)) = compartment.area;
+            if i0 > i1 || j0 > j1 || i1 >= map.tiles.len() || j1 >= map.tiles[0].len() {
+                errors.push(format!("{} area is out of map", compartment.name));
+                continue;
+            }
+            for &(i, j) in compartment.refuges.iter() {
+                if !compartment.contains((i, j)) {
+                    errors.push(format!(
+                        "{} refuge ({}, {}) is outside the area",
+                        compartment.name, i, j
+                    ));
+                } else if !matches!(map.tiles[i][j], TileType::Floor | TileType::SavePlace) {
+                    errors.push(format!(
+                        "{} refuge ({}, {}) is not a floor",
+                        compartment.name, i, j
+                    ));
+                }
+            }
+        }
+        errors
+    }
+
+    pub fn check_vents(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for vent in self.vents.iter() {
+            let Some(map) = self.maps.get(&vent.storey) else {
+                errors.push(format!("no storey named {}", vent.storey.name));
+                continue;
+            };
+            let (i, j) = vent.tile;
+            if i >= map.tiles.len() || j >= map.tiles[0].len() {
+                errors.push(format!(
+                    "vent ({}, {}) is out of {}",
+                    i, j, vent.storey.name
+                ));
+            }
+        }
+        errors
+    }
+
+    //检查竖井的开口是否都在存在的楼层上，每层最多一个开口
+    pub fn check_shafts(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for shaft in self.shafts.iter() {
+            for (n, opening) in shaft.openings.iter().enumerate() {
+                let (i, j) = opening.tile;
+                if shaft.openings[..n]
+                    .iter()
+                    .any(|x| x.storey == opening.storey)
+                {
+                    errors.push(format!(
+                        "{} opens twice on {}",
+                        shaft.name, opening.storey.name
+                    ));
+                }
+                match self.maps.get(&opening.storey) {
+                    None => errors.push(format!("no storey named {}", opening.storey.name)),
+                    Some(map) => {
+                        if i >= map.tiles.len() || j >= map.tiles[0].len() {
+                            errors.push(format!(
+                                "{} ({}, {}) is out of {}",
+                                shaft.name, i, j, opening.storey.name
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    //某层上的主出口格
+    pub fn main_exits(&self, storey: &MapMarker) -> Vec<(usize, usize)> {
+        self.exits
+            .iter()
+            .filter(|exit| &exit.storey == storey && exit.main)
+            .map(|exit| exit.tile)
+            .collect()
+    }
+
+    //某层某格上的电梯停靠处属于第几部电梯
+    pub fn elevator_at(&self, storey: &MapMarker, tile: (usize, usize)) -> Option<usize> {
+        self.elevators.iter().position(|shaft| {
+            shaft
+                .stops
+                .iter()
+                .any(|stop| &stop.storey == storey && stop.tile == tile)
+        })
+    }
+
+    //电梯停靠的楼层中离首层最近的一层，疏散时电梯把人送到这里
+    pub fn elevator_ground(&self, shaft: &ElevatorShaft) -> Option<usize> {
+        (0..shaft.stops.len()).min_by_key(|&n| self.ground_distance(&shaft.stops[n].storey))
+    }
+
+    //某层上能把人送到更接近首层的楼层的电梯停靠格
+    pub fn elevators_toward_ground(&self, storey: &MapMarker) -> Vec<(usize, usize)> {
+        let distance = self.ground_distance(storey);
+        self.elevators
+            .iter()
+            .filter(|shaft| {
+                self.elevator_ground(shaft)
+                    .is_some_and(|n| self.ground_distance(&shaft.stops[n].storey) < distance)
+            })
+            .flat_map(|shaft| shaft.stops.iter())
+            .filter(|stop| &stop.storey == storey)
+            .map(|stop| stop.tile)
+            .collect()
+    }
+
+    //楼梯连接在某层上的一端，以及它另一端的位置
+    fn stair_ends(&self, storey: &MapMarker) -> impl Iterator<Item = (&StairEnd, &StairEnd)> {
+        let storey = storey.clone();
+        self.stairs.iter().filter_map(move |link| {
+            if link.upper.storey == storey {
+                Some((&link.upper, &link.lower))
+            } else if link.lower.storey == storey {
+                Some((&link.lower, &link.upper))
+            } else {
+                None
+            }
+        })
+    }
+
+    //某层上通向更接近首层的楼层的楼梯格，首层之上向下走，首层之下向上走
+    pub fn stairs_toward_ground(&self, storey: &MapMarker) -> Vec<(usize, usize)> {
+        let distance = self.ground_distance(storey);
+        self.stair_ends(storey)
+            .filter(|(_, other)| self.ground_distance(&other.storey) < distance)
+            .map(|(here, _)| here.tile)
+            .collect()
+    }
+
+    //从某层的楼梯格走向首层方向到达的位置
+    pub fn stair_toward_ground(
+        &self,
+        storey: &MapMarker,
+        tile: (usize, usize),
+    ) -> Option<&StairEnd> {
+        let distance = self.ground_distance(storey);
+        self.stair_ends(storey)
+            .find(|(here, other)| {
+                here.tile == tile && self.ground_distance(&other.storey) < distance
+            })
+            .map(|(_, other)| other)
     }
 
     pub fn into_string_map(&self) -> HashMap<String, Map> {
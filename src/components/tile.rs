@@ -21,6 +21,7 @@ pub enum TileType {
     Furniture,
     Stone,
     SavePlace,
+    Black,
 }
 
 //用于加载当前的色块套件，每种类型对应一个图片资源，考虑了使用不同套件可以更换图片
@@ -74,6 +75,7 @@ impl Default for TilesSet {
         tiles.insert(TileType::Furniture, "bed".to_string());
         tiles.insert(TileType::Stone, "floor3".to_string());
         tiles.insert(TileType::SavePlace, "stone".to_string());
+        tiles.insert(TileType::Black, "black".to_string());
         TilesSet {
             tiles,
             now_type: TileType::Wall,
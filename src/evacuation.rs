@@ -1,10 +1,13 @@
+pub mod batch;
 pub mod rules;
+pub mod simulation;
 pub mod things;
 
 use bevy::prelude::*;
 
 use crate::evacuation::rules::fire::*;
 use crate::evacuation::rules::human::*;
+use crate::evacuation::rules::simulation::*;
 use crate::evacuation::rules::smoke::*;
 
 use crate::systems::button::*;
@@ -14,11 +17,10 @@ use crate::systems::set::*;
 
 use crate::components::*;
 
-use self::things::fire::BuildingFire;
-
 use self::things::human::*;
-use self::things::smoke::BuildingSmoke;
-use self::things::NeededParameters;
+use self::things::{seed_from_args, NeededParameters, SimRng};
+
+pub use self::simulation::{SimClock, SimEvent, SimResult, Simulation};
 
 pub struct SetUpPlugin;
 
@@ -28,15 +30,18 @@ pub struct FirePlugin;
 
 impl Plugin for SetUpPlugin {
     fn build(&self, app: &mut App) {
+        let mut para = NeededParameters::load("needed_parameters");
+        if let Some(seed) = seed_from_args().unwrap() {
+            para.seed = Some(seed);
+        }
         app.insert_resource(PaperAlbum::default())
-            .insert_resource(MapAlbum::load("building"))
+            .insert_resource(MapAlbum::load("building").unwrap())
             .insert_resource(TilesSet::default())
             .insert_resource(AtomicCommandQueue::new())
-            .insert_resource(BuildingFire::default())
-            .insert_resource(BuildingSmoke::default())
-            .insert_resource(TheCrowd::new())
-            .insert_resource(BuildingPass::default())
-            .insert_resource(NeededParameters::load("needed_parameters"))
+            .insert_resource(Simulation::default())
+            .insert_resource(SimClock::default())
+            .insert_resource(SimRng::new(para.seed))
+            .insert_resource(para)
             .add_event::<Evacuated>()
             .add_event::<Dead>()
             .add_event::<ChangeStorey>()
@@ -51,14 +56,9 @@ impl Plugin for SetUpPlugin {
                     new_paper_album_for_show,
                     spawn_papers,
                     spawn_maps,
-                    init_building_fire,
-                    init_random_fire,
+                    init_simulation,
                     spawn_fire_sprite,
-                    init_building_smoke,
                     spawn_smoke_sprite,
-                    init_building_pass,
-                    init_the_crowd,
-                    init_crowd_target_and_path,
                     spawn_people,
                 )
                     .chain(),
@@ -91,17 +91,16 @@ impl Plugin for FirePlugin {
         app.add_systems(
             Update,
             (
-                update_fire_visibility,
-                ignition,
-                burning,
-                update_smoke_sprite,
-                smoke_diffusion,
-                people_run,
-                to_get_safe,
-                change_storey,
-                dead,
-                is_safe,
-            ),
+                control_sim_clock,
+                run_simulation,
+                (
+                    update_fire_visibility,
+                    update_smoke_sprite,
+                    //换层新生成的sprite要在标记死亡和移除安全的人之前生成
+                    (update_people_sprite, change_storey, dead, is_safe).chain(),
+                ),
+            )
+                .chain(),
         );
     }
 }
@@ -0,0 +1,418 @@
+use serde::{Deserialize, Serialize};
+
+use std::io::Write;
+
+use crate::components::painting::*;
+
+use super::simulation::{SimResult, Simulation};
+use super::things::NeededParameters;
+
+//批量模拟的设置，第i次模拟的随机种子为seed+i
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchConfig {
+    pub runs: usize,
+    pub seed: u64,
+    pub time_step: f32,
+    pub max_time: f32,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        BatchConfig {
+            runs: 100,
+            seed: 0,
+            time_step: 0.1,
+            max_time: 1800.0,
+        }
+    }
+}
+
+//一组数值的统计量，分位数采用最近秩法
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stat {
+    pub mean: f32,
+    pub min: f32,
+    pub max: f32,
+    pub p50: f32,
+    pub p90: f32,
+    pub p95: f32,
+}
+
+impl Stat {
+    pub fn empty() -> Self {
+        Stat {
+            mean: 0.0,
+            min: 0.0,
+            max: 0.0,
+            p50: 0.0,
+            p90: 0.0,
+            p95: 0.0,
+        }
+    }
+
+    pub fn from_values(values: &[f32]) -> Self {
+        if values.is_empty() {
+            return Stat::empty();
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let percentile = |p: f32| {
+            let rank = (p * sorted.len() as f32).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+        Stat {
+            mean: sorted.iter().sum::<f32>() / sorted.len() as f32,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            p50: percentile(0.5),
+            p90: percentile(0.9),
+            p95: percentile(0.95),
+        }
+    }
+}
+
+//多次模拟结果的汇总
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub runs: usize,
+    pub population: Stat,
+    pub dead: Stat,
+    pub evacuated: Stat,
+    pub sheltered: Stat,
+    pub trapped: Stat,
+    pub evacuation_time: Stat,
+    #[serde(default = "Stat::empty")]
+    pub elevator_passengers: Stat,
+    #[serde(default = "Stat::empty")]
+    pub alarm_time: Stat, //只统计警报响起了的模拟
+    #[serde(default = "Stat::empty")]
+    pub fires_extinguished: Stat,
+    #[serde(default = "Stat::empty")]
+    pub exit_flow: Stat,
+    #[serde(default = "Stat::empty")]
+    pub rescued: Stat,
+    #[serde(default = "Stat::empty")]
+    pub in_refuge: Stat,
+}
+
+impl Summary {
+    pub fn from_results(results: &[SimResult]) -> Self {
+        let stat = |f: fn(&SimResult) -> f32| {
+            Stat::from_values(&results.iter().map(f).collect::<Vec<f32>>())
+        };
+        Summary {
+            runs: results.len(),
+            population: stat(|x| x.population as f32),
+            dead: stat(|x| x.dead as f32),
+            evacuated: stat(|x| x.evacuated as f32),
+            sheltered: stat(|x| x.sheltered as f32),
+            trapped: stat(|x| x.trapped as f32),
+            evacuation_time: stat(|x| x.evacuation_time),
+            elevator_passengers: stat(|x| x.elevator_passengers() as f32),
+            alarm_time: Stat::from_values(
+                &results
+                    .iter()
+                    .filter_map(|x| x.alarm_time)
+                    .collect::<Vec<f32>>(),
+            ),
+            fires_extinguished: stat(|x| x.fires_extinguished as f32),
+            exit_flow: stat(|x| x.exit_flow()),
+            rescued: stat(|x| x.rescued() as f32),
+            in_refuge: stat(|x| x.in_refuge as f32),
+        }
+    }
+}
+
+//写入json的完整报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchReport {
+    pub album: String,
+    pub parameters: NeededParameters,
+    pub config: BatchConfig,
+    pub summary: Summary,
+    pub runs: Vec<SimResult>,
+}
+
+//无界面地跑完一次模拟
+pub fn run_once(
+    map_album: &MapAlbum,
+    para: &NeededParameters,
+    seed: u64,
+    time_step: f32,
+    max_time: f32,
+) -> SimResult {
+    let mut para = para.clone();
+    para.seed = Some(seed);
+    let mut simulation = Simulation::new(map_album, &para);
+    simulation.run_to_end(time_step, max_time);
+    simulation.result()
+}
+
+//多线程跑完一批模拟，结果按随机种子排序
+pub fn run_batch(
+    map_album: &MapAlbum,
+    para: &NeededParameters,
+    config: &BatchConfig,
+) -> Vec<SimResult> {
+    let threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let seeds: Vec<u64> = (0..config.runs as u64).map(|i| config.seed + i).collect();
+    let mut results: Vec<SimResult> = std::thread::scope(|scope| {
+        let handles: Vec<_> = seeds
+            .chunks(config.runs.div_ceil(threads).max(1))
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|&seed| {
+                            run_once(map_album, para, seed, config.time_step, config.max_time)
+                        })
+                        .collect::<Vec<SimResult>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    results.sort_by_key(|x| x.seed);
+    results
+}
+
+pub const RESULT_CSV_HEADER: &str =
+    "seed,population,dead,evacuated,sheltered,trapped,evacuation_time,end_time,elevator_passengers,alarm_time,sprinklers_activated,fires_extinguished,exit_flow,rescued,in_refuge";
+
+pub fn result_csv_row(result: &SimResult) -> String {
+    format!(
+        "{},{},{},{},{},{},{:.2},{:.2},{},{},{},{},{:.2},{},{}",
+        result.seed,
+        result.population,
+        result.dead,
+        result.evacuated,
+        result.sheltered,
+        result.trapped,
+        result.evacuation_time,
+        result.end_time,
+        result.elevator_passengers(),
+        result
+            .alarm_time
+            .map(|x| format!("{:.2}", x))
+            .unwrap_or_default(),
+        result.sprinklers_activated,
+        result.fires_extinguished,
+        result.exit_flow(),
+        result.rescued(),
+        result.in_refuge
+    )
+}
+
+//结果保存在./assets/results/下
+pub fn results_path(name: &str, extension: &str) -> String {
+    std::fs::create_dir_all("./assets/results").unwrap();
+    format!("./assets/results/{}.{}", name, extension)
+}
+
+pub fn save_results_csv(results: &[SimResult], name: &str) {
+    let file = std::fs::File::create(results_path(name, "csv")).unwrap();
+    let mut writer = std::io::BufWriter::new(file);
+    writeln!(writer, "{}", RESULT_CSV_HEADER).unwrap();
+    for result in results {
+        writeln!(writer, "{}", result_csv_row(result)).unwrap();
+    }
+}
+
+pub fn save_summary_csv(summary: &Summary, name: &str) {
+    let file = std::fs::File::create(results_path(name, "csv")).unwrap();
+    let mut writer = std::io::BufWriter::new(file);
+    writeln!(writer, "metric,mean,min,max,p50,p90,p95").unwrap();
+    let rows = [
+        ("population", &summary.population),
+        ("dead", &summary.dead),
+        ("evacuated", &summary.evacuated),
+        ("sheltered", &summary.sheltered),
+        ("trapped", &summary.trapped),
+        ("evacuation_time", &summary.evacuation_time),
+        ("elevator_passengers", &summary.elevator_passengers),
+        ("alarm_time", &summary.alarm_time),
+        ("fires_extinguished", &summary.fires_extinguished),
+        ("exit_flow", &summary.exit_flow),
+        ("rescued", &summary.rescued),
+        ("in_refuge", &summary.in_refuge),
+    ];
+    for (metric, stat) in rows {
+        writeln!(
+            writer,
+            "{},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2}",
+            metric, stat.mean, stat.min, stat.max, stat.p50, stat.p90, stat.p95
+        )
+        .unwrap();
+    }
+}
+
+pub fn save_report_json(report: &BatchReport, name: &str) {
+    let file = std::fs::File::create(results_path(name, "json")).unwrap();
+    serde_json::to_writer_pretty(std::io::BufWriter::new(file), report).unwrap();
+}
+
+//参数扫描的一个维度，形如 k=1,2,4（列表）或 p=0.01:0.05:0.01（起点:终点:步长，含终点）
+//起点和步长都是整数时按整数取值，这样整数类型的参数也能按范围扫描；seed由--seed决定，不能扫描
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SweepAxis {
+    pub field: String,
+    pub values: Vec<serde_json::Value>,
+}
+
+impl SweepAxis {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (field, values) = spec
+            .split_once('=')
+            .ok_or(format!("expected <field>=<values>: {}", spec))?;
+        if field.trim() == "seed" {
+            return Err("seed cannot be swept, use --seed instead".to_string());
+        }
+        let number = |s: &str| {
+            s.trim()
+                .parse::<f64>()
+                .map_err(|_| format!("not a number: {}", s))
+        };
+        let values = if values.contains(':') {
+            let range: Vec<&str> = values.split(':').collect();
+            if range.len() != 3 {
+                return Err(format!("expected start:end:step: {}", values));
+            }
+            let (start, end, step) = (number(range[0])?, number(range[1])?, number(range[2])?);
+            if step <= 0.0 {
+                return Err(format!("step must be positive: {}", values));
+            }
+            let n = ((end - start) / step + 1e-9).floor() as usize;
+            let integral = start.fract() == 0.0 && step.fract() == 0.0;
+            (0..=n)
+                .map(|i| match integral {
+                    true => serde_json::Value::from((start + i as f64 * step) as i64),
+                    false => serde_json::Value::from(start + i as f64 * step),
+                })
+                .collect()
+        } else {
+            values
+                .split(',')
+                .map(|s| serde_json::from_str(s.trim()).map_err(|_| format!("bad value: {}", s)))
+                .collect::<Result<Vec<_>, String>>()?
+        };
+        Ok(SweepAxis {
+            field: field.trim().to_string(),
+            values,
+        })
+    }
+}
+
+//参数组合中的一组取值
+pub type SweepPoint = Vec<(String, serde_json::Value)>;
+
+//所有维度的笛卡尔积，每个组合对应一组参数
+pub fn sweep_grid(
+    para: &NeededParameters,
+    axes: &[SweepAxis],
+) -> Result<Vec<(SweepPoint, NeededParameters)>, String> {
+    let mut grid = vec![(Vec::new(), para.clone())];
+    for axis in axes {
+        let mut next = Vec::new();
+        for (point, para) in grid.iter() {
+            for value in axis.values.iter() {
+                let mut point = point.clone();
+                let mut para = para.clone();
+                para.set_field(&axis.field, value.clone())?;
+                point.push((axis.field.clone(), value.clone()));
+                next.push((point, para));
+            }
+        }
+        grid = next;
+    }
+    Ok(grid)
+}
+
+//参数扫描中一个组合的汇总结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepRow {
+    pub point: SweepPoint,
+    pub summary: Summary,
+}
+
+//写入json的参数扫描报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepReport {
+    pub album: String,
+    pub parameters: NeededParameters,
+    pub config: BatchConfig,
+    pub axes: Vec<SweepAxis>,
+    pub rows: Vec<SweepRow>,
+}
+
+//对每个参数组合跑一批模拟
+pub fn run_sweep(
+    map_album: &MapAlbum,
+    para: &NeededParameters,
+    axes: &[SweepAxis],
+    config: &BatchConfig,
+) -> Result<Vec<SweepRow>, String> {
+    let mut rows = Vec::new();
+    for (point, para) in sweep_grid(para, axes)? {
+        let results = run_batch(map_album, &para, config);
+        rows.push(SweepRow {
+            point,
+            summary: Summary::from_results(&results),
+        });
+    }
+    Ok(rows)
+}
+
+//每行一个参数组合，先列出参数取值，再列出各指标的均值和p95
+pub fn save_sweep_csv(axes: &[SweepAxis], rows: &[SweepRow], name: &str) {
+    let file = std::fs::File::create(results_path(name, "csv")).unwrap();
+    let mut writer = std::io::BufWriter::new(file);
+    let mut header: Vec<String> = axes.iter().map(|axis| axis.field.clone()).collect();
+    for metric in [
+        "dead",
+        "evacuated",
+        "sheltered",
+        "trapped",
+        "evacuation_time",
+        "elevator_passengers",
+        "alarm_time",
+        "fires_extinguished",
+        "exit_flow",
+        "rescued",
+        "in_refuge",
+    ] {
+        header.push(format!("{}_mean", metric));
+        header.push(format!("{}_p95", metric));
+    }
+    writeln!(writer, "runs,{}", header.join(",")).unwrap();
+    for row in rows {
+        let summary = &row.summary;
+        let mut cells: Vec<String> = row.point.iter().map(|(_, v)| v.to_string()).collect();
+        for stat in [
+            &summary.dead,
+            &summary.evacuated,
+            &summary.sheltered,
+            &summary.trapped,
+            &summary.evacuation_time,
+            &summary.elevator_passengers,
+            &summary.alarm_time,
+            &summary.fires_extinguished,
+            &summary.exit_flow,
+            &summary.rescued,
+            &summary.in_refuge,
+        ] {
+            cells.push(format!("{:.2}", stat.mean));
+            cells.push(format!("{:.2}", stat.p95));
+        }
+        writeln!(writer, "{},{}", summary.runs, cells.join(",")).unwrap();
+    }
+}
+
+pub fn save_sweep_json(report: &SweepReport, name: &str) {
+    let file = std::fs::File::create(results_path(name, "json")).unwrap();
+    serde_json::to_writer_pretty(std::io::BufWriter::new(file), report).unwrap();
+}
@@ -1,3 +1,4 @@
 pub mod fire;
 pub mod human;
+pub mod simulation;
 pub mod smoke;
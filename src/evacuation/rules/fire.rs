@@ -1,6 +1,7 @@
 use crate::components::painting::*;
-use crate::evacuation::things::NeededParameters;
-use crate::evacuation::things::{fire::*, smoke::*};
+use crate::evacuation::things::{NeededParameters, SimRng};
+use crate::evacuation::simulation::Simulation;
+use crate::evacuation::things::fire::*;
 use crate::systems::paper::{get_x, get_y};
 use crate::components::TileType;
 use bevy::prelude::*;
@@ -11,10 +12,12 @@ use rand::Rng;
 pub fn random_replace_furniture(
     mut map_album: ResMut<MapAlbum>,
     para: Res<NeededParameters>,
+    mut rng: ResMut<SimRng>,
 ){
     let c = para.c;
-    let mut rng = rand::thread_rng();
-    for (_, map) in map_album.maps.iter_mut() {
+    let rng = &mut rng.rng;
+    for marker in sorted_markers(&map_album.maps) {
+        let map = map_album.maps.get_mut(&marker).unwrap();
         for i in 0..map.tiles.len() {
             for j in 0..map.tiles[0].len() {
                 if map.tiles[i][j] == TileType::Furniture {
@@ -33,44 +36,15 @@ pub fn random_replace_furniture(
 }
 
 
-//初始化building_fire
-pub fn init_building_fire(
-    mut building_fire: ResMut<BuildingFire>,
-    map_album: ResMut<MapAlbum>,
-    para: Res<NeededParameters>,
-) {
-    for (marker, map) in map_album.maps.iter() {
-        let k = para.k;
-        let burning_time = para.burning_time;
-        let smoking_time = para.smoking_time;
-        let mut storey_fire = StoreyFire::new_with(
-            map.tiles.len(),
-            map.tiles[0].len(),
-            burning_time,
-            smoking_time,
-            k,
-        );
-        storey_fire.init_from_map(map);
-        building_fire.maps.insert(marker.clone(), storey_fire);
-    }
-}
-
-//初始随即点燃
-pub fn init_random_fire(mut building_fire: ResMut<BuildingFire>, para: Res<NeededParameters>) {
-    let p = para.p;
-    for (_, storey_fire) in building_fire.maps.iter_mut() {
-        storey_fire.get_p_fire(p as f64);
-    }
-}
-
 //生成火,初始均不可见
 pub fn spawn_fire_sprite(
     mut commands: Commands,
-    building_fire: ResMut<BuildingFire>,
+    simulation: Res<Simulation>,
     papers: Res<PaperAlbum>,
     query: Query<(Entity, &PaperMarker)>,
     asset_server: Res<AssetServer>,
 ) {
+    let building_fire = &simulation.fire;
     for (entity, papermarker) in query.iter() {
         let map = &building_fire.maps[&papermarker.map];
         let now_paper = papers
@@ -119,19 +93,13 @@ pub fn spawn_fire_sprite(
     }
 }
 
-//燃烧时发生的事情：引燃
-pub fn ignition(mut building_fire: ResMut<BuildingFire>) {
-    for (_, storey_fire) in building_fire.maps.iter_mut() {
-        storey_fire.get_neighbours_fire_on();
-    }
-}
-
 //更新火焰的可见性
 pub fn update_fire_visibility(
     mut query: Query<(&FireTile, &mut Sprite, &MapMarker)>,
-    building_fire: Res<BuildingFire>,
+    simulation: Res<Simulation>,
     map_album: ResMut<MapAlbum>,
 ) {
+    let building_fire = &simulation.fire;
     let map_now_marker = map_album.now_map.clone();
     for (fire_tile, mut sprite, marker) in query.iter_mut() {
         if marker != &map_now_marker {
@@ -149,34 +117,3 @@ pub fn update_fire_visibility(
         }
     }
 }
-
-//燃烧时发生的事情：计时器前进，产生烟
-pub fn burning(
-    mut building_fire: ResMut<BuildingFire>,
-    mut building_smoke: ResMut<BuildingSmoke>,
-    para: Res<NeededParameters>,
-    time: Res<Time>,
-) {
-    let s = para.s;
-    for (marker, storey_fire) in building_fire.maps.iter_mut() {
-        let storey_smoke = building_smoke.maps.get_mut(marker).unwrap();
-        for i in 0..storey_fire.map.len() {
-            for j in 0..storey_fire.map[0].len() {
-                let fire = &mut storey_fire.map[i][j];
-                if let Fire::On(timer) = fire {
-                    timer.burning_timer.tick(time.delta());
-                    timer.smoking_timer.tick(time.delta());
-                    if timer.smoking_timer.finished() {
-                        storey_smoke.map[i][j].density_up(
-                            s * timer.burning_timer.elapsed_secs()
-                                / timer.burning_timer.duration().as_secs_f32(),
-                        );
-                    }
-                    if timer.burning_timer.finished() {
-                        fire.fire_off();
-                    }
-                }
-            }
-        }
-    }
-}
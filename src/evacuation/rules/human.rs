@@ -1,101 +1,69 @@
 use bevy::prelude::*;
 
+use std::collections::{BTreeSet, HashMap};
+
 use crate::components::painting::*;
+use crate::evacuation::simulation::Simulation;
 use crate::evacuation::things::human::*;
-use crate::evacuation::things::smoke::*;
-use crate::systems::paper;
-use crate::evacuation::things::NeededParameters;
 
-pub fn init_building_pass(mut building_pass: ResMut<BuildingPass>, map_album: Res<MapAlbum>) {
-    for (marker, map) in map_album.maps.iter() {
-        let mut storey_pass = StoreyPass::new(map.tiles.len(), map.tiles[0].len());
-        storey_pass.init_from_map(map);
-        building_pass.maps.insert(marker.clone(), storey_pass);
+//人的sprite，坐标由格坐标乘以element_size得到
+fn human_sprite(
+    human: &Human,
+    texture: Handle<Image>,
+    element_size: f32,
+    color: Color,
+) -> SpriteBundle {
+    let mut translation = human.position * element_size;
+    translation.z = 12.0;
+    SpriteBundle {
+        texture,
+        sprite: Sprite {
+            custom_size: Some(Vec2::new(0.8 * element_size, 0.8 * element_size)),
+            color,
+            ..Default::default()
+        },
+        visibility: Visibility::Inherited,
+        transform: Transform {
+            translation,
+            ..Default::default()
+        },
+        ..Default::default()
     }
 }
 
-pub fn init_the_crowd(
-    mut the_crowd: ResMut<TheCrowd>,
-    map_album: Res<MapAlbum>,
-    paper_album: Res<PaperAlbum>,
-    para: Res<NeededParameters>,
-) {
-    let human_seed = para.human_seed;
-    let v = para.v;
-    let h = para.h;
-    for (marker, pass) in map_album.maps.iter() {
-        the_crowd.extend_from_pass(pass, marker.clone(), human_seed, v, h);
-    }
-    let paper = &paper_album.papers[0];
-    the_crowd.element_size = paper.element_size;
-    the_crowd.board_width = paper.width;
-    the_crowd.board_height = paper.height;
-}
-
-pub fn init_crowd_target_and_path(
-    mut the_crowd: ResMut<TheCrowd>,
-    building_pass: Res<BuildingPass>,
-) {
-    let size = the_crowd.element_size;
-    let width = the_crowd.board_width;
-    let height = the_crowd.board_height;
-    for human in the_crowd.humans.iter_mut() {
-        let pass = &building_pass.maps[&human.storey];
-        human.find_my_target(pass);
-        human.find_my_path(pass);
-        human.next_tile = *human.my_path.front().unwrap();
-        human.clc_position(size, width, height);
-        human.clc_next_position(size, width, height);
-        human.change_my_direction();
+//地面层的人和其他楼层的人用不同颜色区分
+fn storey_color(simulation: &Simulation, storey: &MapMarker) -> Color {
+    if simulation.map_album.is_ground(storey) {
+        Color::rgba(0.8, 0.6, 0.6, 1.0)
+    } else {
+        Color::rgba(0.6, 1.0, 0.6, 1.0)
     }
 }
 
 pub fn spawn_people(
     mut commands: Commands,
-    the_crowd: Res<TheCrowd>,
+    simulation: Res<Simulation>,
+    papers: Res<PaperAlbum>,
     query: Query<(Entity, &PaperMarker)>,
     asset_server: Res<AssetServer>,
 ) {
+    let the_crowd = &simulation.crowd;
     for (entity, papermarker) in query.iter() {
-        let element_size = the_crowd.element_size;
-        let board_width = the_crowd.board_width;
-        let board_height = the_crowd.board_height;
-        let path = the_crowd.texture_path.clone();
-
-        let f1 = MapMarker {
-            name: "f1".to_string(),
-        };
-
+        let element_size = papers
+            .papers
+            .iter()
+            .find(|paper| paper.map == papermarker.map)
+            .unwrap()
+            .element_size;
         commands.entity(entity).with_children(|builder| {
             for human in the_crowd.humans.iter() {
                 if human.storey != papermarker.map {
                     continue;
                 }
+                let color = storey_color(&simulation, &human.storey);
+                let path = the_crowd.texture_of(human);
                 builder.spawn((
-                    SpriteBundle {
-                        texture: asset_server.load(&path),
-                        sprite: Sprite {
-                            custom_size: Some(Vec2::new(0.8 * element_size, 0.8 * element_size)),
-                            color: {
-                                if human.storey == f1 {
-                                    Color::rgba(0.8, 0.6, 0.6, 1.0)
-                                } else {
-                                    Color::rgba(0.6, 1.0, 0.6, 1.0)
-                                }
-                            },
-                            ..Default::default()
-                        },
-                        visibility: Visibility::Inherited,
-                        transform: Transform {
-                            translation: Vec3::new(
-                                paper::get_x(element_size, human.now_tile.1, board_width),
-                                paper::get_y(element_size, human.now_tile.0, board_height),
-                                12.0,
-                            ),
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    },
+                    human_sprite(human, asset_server.load(path), element_size, color),
                     HumanMarker::from_human(human),
                 ));
             }
@@ -103,74 +71,30 @@ pub fn spawn_people(
     }
 }
 
-pub fn people_run(
-    mut query: Query<(&mut Transform, &HumanMarker)>,
-    smoke: Res<BuildingSmoke>,
-    mut the_crowd: ResMut<TheCrowd>,
-    time: Res<Time>,
-    mut event1: EventWriter<Dead>,
-    mut event2: EventWriter<Evacuated>,
-    mut event3: EventWriter<ChangeStorey>,
-    mut event4: EventWriter<ChangeSafe>,
+//根据模拟结果更新人的位置，状态变化时换图片
+pub fn update_people_sprite(
+    mut query: Query<(&mut Transform, &mut Handle<Image>, &mut HumanMarker)>,
+    simulation: Res<Simulation>,
+    papers: Res<PaperAlbum>,
+    asset_server: Res<AssetServer>,
 ) {
-    let size = the_crowd.element_size;
-    let width = the_crowd.board_width;
-    let height = the_crowd.board_height;
-    for (mut transform, human) in query.iter_mut() {
-        let id = human.id;
-        let human = &mut the_crowd.humans[id];
-        let storey_smoke = &smoke.maps[&human.storey];
-
-        human.smoke_damage(storey_smoke, time.delta_seconds());
-
-        if human.hp <= 0.0 {
-            human.is_dead = true;
-            event1.send(Dead { id: id });
+    let sizes: HashMap<&MapMarker, f32> = papers
+        .papers
+        .iter()
+        .map(|paper| (&paper.map, paper.element_size))
+        .collect();
+    for (mut transform, mut texture, mut marker) in query.iter_mut() {
+        let human = &simulation.crowd.humans[marker.id];
+        if human.storey != marker.storey {
             continue;
         }
-
-        if transform.translation == human.next_position {
-            if let Some(p) = human.my_path.pop_front() {
-                human.now_tile = p;
-                if human.now_tile == human.target_tile {
-                    let f1 = MapMarker {
-                        name: "f1".to_string(),
-                    };
-                    let f2 = MapMarker {
-                        name: "f2".to_string(),
-                    };
-                    if human.is_evacuated == false {
-                        if human.storey == f1 {
-                            event2.send(Evacuated { id: id });
-                            human.is_evacuated = true;
-                        } else if human.storey == f2 {
-                            event3.send(ChangeStorey { id: id });
-                        }
-                    } else if human.is_evacuated == true {
-                        human.is_safe = true;
-                        event4.send(ChangeSafe { id: id });
-                        continue;
-                    }
-                }
-            }
-
-            if let Some(next_tile) = human.my_path.front() {
-                human.next_tile = *next_tile;
-                human.position = human.next_position;
-                human.clc_next_position(size, width, height);
-                human.change_my_direction();
-            }
-        }
-
-        human.change_my_speed();
-        let newpos = transform.translation + human.direction * human.speed * time.delta_seconds();
-        let dis0 = transform.translation.distance(newpos);
-        let dis1 = transform.translation.distance(human.next_position);
-        if dis0 >= dis1 {
-            transform.translation = human.next_position;
-        } else {
-            transform.translation = newpos;
+        if !human.is_dead && human.behaviour != marker.behaviour {
+            marker.behaviour = human.behaviour;
+            *texture = asset_server.load(simulation.crowd.texture_of(human));
         }
+        let mut translation = human.position * sizes[&human.storey];
+        translation.z = 12.0;
+        transform.translation = translation;
     }
 }
 
@@ -187,27 +111,6 @@ pub fn dead(mut events: EventReader<Dead>, mut query: Query<(&mut Sprite, &Human
     }
 }
 
-pub fn to_get_safe(
-    mut the_crowd: ResMut<TheCrowd>,
-    map_album: Res<MapAlbum>,
-    mut events: EventReader<Evacuated>,
-) {
-    for event in events.read() {
-        let id = event.id;
-        let size = the_crowd.element_size;
-        let width = the_crowd.board_width;
-        let height = the_crowd.board_height;
-        let human = &mut the_crowd.humans[id];
-        let storey = &map_album.maps[&human.storey];
-        human.find_my_safe_place(storey);
-        human.find_my_safe_path(storey);
-        human.next_tile = *human.my_path.front().unwrap();
-        human.clc_position(size, width, height);
-        human.clc_next_position(size, width, height);
-        human.change_my_direction();
-    }
-}
-
 pub fn is_safe(
     mut commands: Commands,
     mut events: EventReader<ChangeSafe>,
@@ -223,25 +126,21 @@ pub fn is_safe(
     }
 }
 
+//换层后把人的sprite移到新楼层的paper上
+//一帧里可能推进好几步，同一个人换了几次楼层时只按他最后所在的楼层生成一次sprite
 pub fn change_storey(
-    mut the_crowd: ResMut<TheCrowd>,
-    building_pass: Res<BuildingPass>,
+    simulation: Res<Simulation>,
+    papers: Res<PaperAlbum>,
     mut events: EventReader<ChangeStorey>,
     mut commands: Commands,
     query: Query<(Entity, &HumanMarker)>,
     the_papers: Query<(Entity, &PaperMarker)>,
     asset_server: Res<AssetServer>,
 ) {
-    for event in events.read() {
-        let id = event.id;
-        let element_size = the_crowd.element_size;
-        let board_width = the_crowd.board_width;
-        let board_height = the_crowd.board_height;
-        let path = the_crowd.texture_path.clone();
-        let human = &mut the_crowd.humans[id];
-        human.storey = MapMarker {
-            name: "f1".to_string(),
-        };
+    let ids: BTreeSet<usize> = events.read().map(|event| event.id).collect();
+    for id in ids {
+        let human = &simulation.crowd.humans[id];
+        let path = simulation.crowd.texture_of(human);
         for (entity, humanmarker) in query.iter() {
             if humanmarker.id == id {
                 commands.entity(entity).despawn_recursive();
@@ -252,37 +151,23 @@ pub fn change_storey(
             if papermarker.map != human.storey {
                 continue;
             }
+            let element_size = papers
+                .papers
+                .iter()
+                .find(|paper| paper.map == papermarker.map)
+                .unwrap()
+                .element_size;
             commands.entity(entity).with_children(|builder| {
                 builder.spawn((
-                    SpriteBundle {
-                        texture: asset_server.load(&path),
-                        sprite: Sprite {
-                            custom_size: Some(Vec2::new(0.8 * element_size, 0.8 * element_size)),
-                            color: Color::rgba(0.6, 1.0, 0.6, 1.0),
-                            ..Default::default()
-                        },
-                        visibility: Visibility::Inherited,
-                        transform: Transform {
-                            translation: Vec3::new(
-                                paper::get_x(element_size, human.now_tile.1, board_width),
-                                paper::get_y(element_size, human.now_tile.0, board_height),
-                                12.0,
-                            ),
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    },
+                    human_sprite(
+                        human,
+                        asset_server.load(&path),
+                        element_size,
+                        storey_color(&simulation, &human.storey),
+                    ),
                     HumanMarker::from_human(human),
                 ));
             });
-
-            let storey = &building_pass.maps[&human.storey];
-            human.find_my_target(storey);
-            human.find_my_path(storey);
-            human.next_tile = *human.my_path.front().unwrap();
-            human.clc_position(element_size, board_width, board_height);
-            human.clc_next_position(element_size, board_width, board_height);
-            human.change_my_direction();
         }
     }
 }
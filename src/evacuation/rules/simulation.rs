@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+
+use crate::components::painting::*;
+use crate::evacuation::simulation::*;
+use crate::evacuation::things::human::*;
+use crate::evacuation::things::{NeededParameters, SimRng};
+
+//根据地图和参数建立模拟
+pub fn init_simulation(
+    mut simulation: ResMut<Simulation>,
+    map_album: Res<MapAlbum>,
+    para: Res<NeededParameters>,
+    rng: Res<SimRng>,
+) {
+    info!("seed: {}", rng.seed);
+    *simulation = Simulation::with_rng(&map_album, &para, rng.clone());
+}
+
+//模拟时钟的键盘控制：空格暂停/继续，N单步，=加速，-减速
+pub fn control_sim_clock(keyboard_input: Res<ButtonInput<KeyCode>>, mut clock: ResMut<SimClock>) {
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        clock.toggle_pause();
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyN) {
+        clock.request_step();
+    }
+    if keyboard_input.just_pressed(KeyCode::Equal) {
+        clock.speed_up();
+    }
+    if keyboard_input.just_pressed(KeyCode::Minus) {
+        clock.speed_down();
+    }
+}
+
+//按模拟时钟以固定步长推进模拟，并把模拟事件转换为bevy事件供显示层使用
+pub fn run_simulation(
+    mut simulation: ResMut<Simulation>,
+    mut clock: ResMut<SimClock>,
+    time: Res<Time>,
+    mut event1: EventWriter<Dead>,
+    mut event2: EventWriter<Evacuated>,
+    mut event3: EventWriter<ChangeStorey>,
+    mut event4: EventWriter<ChangeSafe>,
+) {
+    let steps = clock.advance(time.delta_seconds());
+    let mut events = Vec::new();
+    for _ in 0..steps {
+        events.extend(simulation.step(clock.time_step));
+    }
+    for event in events {
+        match event {
+            SimEvent::Dead(id) => {
+                event1.send(Dead { id });
+            }
+            SimEvent::Evacuated(id) => {
+                event2.send(Evacuated { id });
+            }
+            SimEvent::ChangeStorey(id) => {
+                event3.send(ChangeStorey { id });
+            }
+            SimEvent::Safe(id) => {
+                event4.send(ChangeSafe { id });
+            }
+        }
+    }
+}
@@ -1,27 +1,20 @@
 use bevy::prelude::*;
 
 use crate::components::painting::*;
+use crate::evacuation::simulation::Simulation;
 use crate::evacuation::things::smoke::*;
 
 use crate::systems::paper::{get_x, get_y};
 
-//初始化building_smoke
-pub fn init_building_smoke(mut building_smoke: ResMut<BuildingSmoke>, map_album: ResMut<MapAlbum>) {
-    for (marker, map) in map_album.maps.iter() {
-        let mut storey_smoke = StoreySmoke::new(map.tiles.len(), map.tiles[0].len());
-        storey_smoke.init_from_map(map);
-        building_smoke.maps.insert(marker.clone(), storey_smoke);
-    }
-}
-
 //生成烟雾
 pub fn spawn_smoke_sprite(
     mut commands: Commands,
-    building_smoke: ResMut<BuildingSmoke>,
+    simulation: Res<Simulation>,
     papers: Res<PaperAlbum>,
     query: Query<(Entity, &PaperMarker)>,
     asset_server: Res<AssetServer>,
 ) {
+    let building_smoke = &simulation.smoke;
     for (entity, papermarker) in query.iter() {
         let map = &building_smoke.maps[&papermarker.map];
         let now_paper = papers
@@ -39,7 +32,7 @@ pub fn spawn_smoke_sprite(
         commands.entity(entity).with_children(|builder| {
             for i in 0..row as usize {
                 for j in 0..column as usize {
-                    if let Smoke::InDiffusible = map.map[i][j] {
+                    if let Smoke::InDiffusible | Smoke::Vent = map.map[i][j] {
                         continue;
                     }
                     builder.spawn((
@@ -73,9 +66,10 @@ pub fn spawn_smoke_sprite(
 //更新烟雾sprite的透明度
 pub fn update_smoke_sprite(
     mut query: Query<(&mut Sprite, &SmokeTile, &MapMarker)>,
-    building_smoke: Res<BuildingSmoke>,
+    simulation: Res<Simulation>,
     map_album: Res<MapAlbum>,
 ) {
+    let building_smoke = &simulation.smoke;
     let marker_now = map_album.now_map.clone();
     for (mut sprite, tile, marker) in query.iter_mut() {
         if marker != &marker_now {
@@ -90,10 +84,3 @@ pub fn update_smoke_sprite(
         sprite.color.set_a(value);
     }
 }
-
-//烟雾扩散
-pub fn smoke_diffusion(mut building_smoke: ResMut<BuildingSmoke>) {
-    for (_, storey_smoke) in building_smoke.maps.iter_mut() {
-        storey_smoke.diffuse();
-    }
-}
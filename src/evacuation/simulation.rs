@@ -0,0 +1,929 @@
+use bevy::prelude::*;
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::components::painting::*;
+use crate::components::TileType;
+
+use super::things::alarm::BuildingAlarm;
+use super::things::behaviour::Behaviour;
+use super::things::compartment::{BuildingCompartments, CompartmentOutcome, HorizontalMode};
+use super::things::door::BuildingDoors;
+use super::things::elevator::{BuildingElevators, ElevatorUsage};
+use super::things::fire::BuildingFire;
+use super::things::heat::BuildingHeat;
+use super::things::human::*;
+use super::things::occupancy::{BuildingOccupancy, FlowKind, TileFlow};
+use super::things::profile::{OccupantProfiles, ProfileOutcome};
+use super::things::rescue::{BuildingRescue, RescuePlan, RescueRecord, Role};
+use super::things::smoke::{BuildingSmoke, SmokeBalance};
+use super::things::speed::{speed_factor, StairWay};
+use super::things::stairwell::{BuildingStairwells, StairwellOutcome};
+use super::things::suppression::BuildingSuppression;
+use super::things::toxicity::{AgentDose, ToxicityModel};
+use super::things::{NeededParameters, SimRng};
+
+//模拟过程中产生的事件，由显示层转换为bevy事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimEvent {
+    Dead(usize),
+    Evacuated(usize),
+    ChangeStorey(usize),
+    Safe(usize),
+}
+
+//模拟倍速可选的档位
+pub const CLOCK_SPEEDS: [f32; 6] = [0.5, 1.0, 2.0, 5.0, 10.0, 20.0];
+
+//模拟时钟：以固定的物理步长推进模拟，与帧率无关，可以暂停、单步和调整倍速
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct SimClock {
+    pub time_step: f32,
+    pub speed_level: usize,
+    pub paused: bool,
+    pub step_once: bool,
+    pub accumulator: f32,
+    pub max_steps_per_frame: usize,
+}
+
+impl Default for SimClock {
+    fn default() -> Self {
+        SimClock::new(0.1)
+    }
+}
+
+impl SimClock {
+    pub fn new(time_step: f32) -> Self {
+        SimClock {
+            time_step,
+            speed_level: 1,
+            paused: false,
+            step_once: false,
+            accumulator: 0.0,
+            max_steps_per_frame: 200,
+        }
+    }
+
+    pub fn speed(&self) -> f32 {
+        CLOCK_SPEEDS[self.speed_level]
+    }
+
+    pub fn speed_up(&mut self) {
+        self.speed_level = (self.speed_level + 1).min(CLOCK_SPEEDS.len() - 1);
+    }
+
+    pub fn speed_down(&mut self) {
+        self.speed_level = self.speed_level.saturating_sub(1);
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.accumulator = 0.0;
+    }
+
+    //暂停时前进一个步长
+    pub fn request_step(&mut self) {
+        if self.paused {
+            self.step_once = true;
+        }
+    }
+
+    //根据真实经过的时间，返回这一帧应该推进的步数，积累不足一步的时间留到下一帧
+    pub fn advance(&mut self, real_delta: f32) -> usize {
+        if self.paused {
+            let steps = self.step_once as usize;
+            self.step_once = false;
+            return steps;
+        }
+        self.accumulator += real_delta * self.speed();
+        let steps = (self.accumulator / self.time_step) as usize;
+        self.accumulator -= steps as f32 * self.time_step;
+        if steps > self.max_steps_per_frame {
+            self.accumulator = 0.0;
+            return self.max_steps_per_frame;
+        }
+        steps
+    }
+}
+
+//一次模拟的结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimResult {
+    pub seed: u64,
+    pub population: usize,
+    pub dead: usize,
+    pub evacuated: usize,
+    pub sheltered: usize,     //疏散后到达安全区的人数
+    pub trapped: usize,       //模拟结束时既未死亡也未疏散的人数
+    pub evacuation_time: f32, //最后一人疏散出建筑的时间
+    pub end_time: f32,
+    #[serde(default)]
+    pub alarm_time: Option<f32>, //最早响起警报的时间
+    #[serde(default)]
+    pub sprinklers_activated: usize, //开始喷水的喷头数
+    #[serde(default)]
+    pub fires_extinguished: usize, //被喷淋扑灭的燃烧格数
+    #[serde(default)]
+    pub doors_failed: usize, //被火烧穿的门格数
+    #[serde(default)]
+    pub peak_temperature: f32, //模拟过程中出现过的最高气温（°C）
+    #[serde(default)]
+    pub smoke: SmokeBalance, //全楼累计产生、排出楼外和结束时留在楼内的烟雾量
+    #[serde(default)]
+    pub stairwells: Vec<StairwellOutcome>, //每部楼梯被烟污染的时间和梯间烟雾随时间的变化
+    #[serde(default)]
+    pub doses: Vec<AgentDose>, //每个人累计的剂量
+    #[serde(default)]
+    pub elevators: Vec<ElevatorUsage>, //每部电梯的运行次数、运送人数和锁定时间
+    #[serde(default)]
+    pub flows: Vec<TileFlow>, //每个门格和出口格的通过人数和时间
+    #[serde(default)]
+    pub rescues: Vec<RescueRecord>, //每个被员工协助疏散的病人的派遣、背起和送达时间
+    #[serde(default)]
+    pub profiles: Vec<ProfileOutcome>, //每类人员的人数、死亡和疏散人数
+    #[serde(default)]
+    pub in_refuge: usize, //模拟结束时在避难区里避难的人数
+    #[serde(default)]
+    pub compartments: Vec<CompartmentOutcome>, //每个防烟分区失去安全的时间和避难人数
+}
+
+impl SimResult {
+    //乘电梯疏散的总人数
+    pub fn elevator_passengers(&self) -> usize {
+        self.elevators.iter().map(|x| x.passengers).sum()
+    }
+
+    //被员工送出去的病人数
+    pub fn rescued(&self) -> usize {
+        self.rescues
+            .iter()
+            .filter(|x| x.delivered_at.is_some())
+            .count()
+    }
+
+    //所有出口合计的平均流量（人/秒）
+    pub fn exit_flow(&self) -> f32 {
+        let exits: Vec<&TileFlow> = self
+            .flows
+            .iter()
+            .filter(|x| x.kind == FlowKind::Exit)
+            .collect();
+        let passed: usize = exits.iter().map(|x| x.passed).sum();
+        let first = exits.iter().map(|x| x.first).fold(f32::MAX, f32::min);
+        let last = exits.iter().map(|x| x.last).fold(0.0, f32::max);
+        if passed > 1 && last > first {
+            (passed - 1) as f32 / (last - first)
+        } else {
+            0.0
+        }
+    }
+}
+
+//不依赖bevy渲染的模拟引擎，火、烟、通行和人群都由它推进
+//坐标以格为单位，显示时乘以paper的element_size
+#[derive(Resource, Debug, Clone)]
+pub struct Simulation {
+    pub map_album: MapAlbum,
+    pub para: NeededParameters,
+    pub fire: BuildingFire,
+    pub smoke: BuildingSmoke,
+    pub stairwells: BuildingStairwells,
+    pub heat: BuildingHeat,
+    pub doors: BuildingDoors,
+    pub alarm: BuildingAlarm,
+    pub suppression: BuildingSuppression,
+    pub compartments: BuildingCompartments,
+    pub pass: BuildingPass,
+    pub evacuation: BuildingEvacuation,
+    pub elevators: BuildingElevators,
+    pub occupancy: BuildingOccupancy,
+    pub rescue: BuildingRescue,
+    pub crowd: TheCrowd,
+    pub rng: SimRng,
+    pub time: f32,
+    pub evacuation_time: f32,
+    pub next_reroute: f32,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Simulation {
+            map_album: MapAlbum::new(),
+            para: NeededParameters::default(),
+            fire: BuildingFire::default(),
+            smoke: BuildingSmoke::default(),
+            stairwells: BuildingStairwells::default(),
+            heat: BuildingHeat::default(),
+            doors: BuildingDoors::default(),
+            alarm: BuildingAlarm::default(),
+            suppression: BuildingSuppression::default(),
+            compartments: BuildingCompartments::default(),
+            pass: BuildingPass::default(),
+            evacuation: BuildingEvacuation::default(),
+            elevators: BuildingElevators::default(),
+            occupancy: BuildingOccupancy::default(),
+            rescue: BuildingRescue::default(),
+            crowd: TheCrowd::new(),
+            rng: SimRng::default(),
+            time: 0.0,
+            evacuation_time: 0.0,
+            next_reroute: 0.0,
+        }
+    }
+}
+
+impl Simulation {
+    //随机种子取自para.seed
+    pub fn new(map_album: &MapAlbum, para: &NeededParameters) -> Self {
+        Simulation::with_rng(map_album, para, SimRng::new(para.seed))
+    }
+
+    //沿用已有的随机数发生器，保证整个过程只有一个随机数序列
+    pub fn with_rng(map_album: &MapAlbum, para: &NeededParameters, mut rng: SimRng) -> Self {
+        let mut fire = BuildingFire::default();
+        fire.init_from_album(map_album, para);
+        fire.get_p_fire(para.p as f64, &mut rng.rng);
+
+        let mut smoke = BuildingSmoke::default();
+        smoke.init_from_album(map_album);
+
+        let mut stairwells = BuildingStairwells::default();
+        stairwells.init_from_album(map_album, para);
+
+        let mut heat = BuildingHeat::default();
+        heat.init_from_album(map_album, para);
+
+        let mut doors = BuildingDoors::default();
+        doors.init_from_album(map_album, para, &mut fire, &mut smoke);
+
+        let mut alarm = BuildingAlarm::default();
+        alarm.init_from_album(map_album, para.alarm_scope);
+
+        let mut suppression = BuildingSuppression::default();
+        suppression.init_from_album(map_album, para);
+
+        let mut pass = BuildingPass::default();
+        pass.init_from_album(map_album);
+
+        //不水平疏散时没有分区，通行图里也就没有避难格
+        let mut compartments = BuildingCompartments::default();
+        if para.horizontal_evacuation != HorizontalMode::Off {
+            compartments.init_from_album(map_album);
+            compartments.update(&fire, &smoke, para, 0.0);
+            compartments.update_pass(&mut pass);
+        }
+
+        let mut elevators = BuildingElevators::default();
+        elevators.init_from_album(map_album);
+
+        let mut occupancy = BuildingOccupancy::default();
+        occupancy.init_from_album(map_album);
+
+        let mut crowd = TheCrowd::new();
+        crowd.init_from_album(map_album, para.human_seed, para.v, para.h, &mut rng.rng);
+        crowd.assign_pre_movement(para.pre_movement, para.pre_movement_spread, &mut rng.rng);
+        //配置了人员类型时员工、病人、访客和乘电梯的人都由人员类型决定，不再按比例随机抽
+        if para.profiles.is_empty() {
+            crowd.assign_elevator_users(para.elevator_ratio, &mut rng.rng);
+            crowd.assign_familiarity(para.visitor_ratio, &mut rng.rng);
+            crowd.assign_staff(para.staff_ratio, &mut rng.rng);
+            crowd.assign_bedridden(para.bedridden_ratio, &mut rng.rng);
+        } else {
+            let profiles = OccupantProfiles::load(&para.profiles);
+            crowd.assign_profiles(&profiles, map_album, &mut rng.rng);
+        }
+
+        let mut evacuation = BuildingEvacuation::default();
+        evacuation.update_from_pass(&pass, para.smoke_route_weight);
+        crowd.init_target_and_path(&pass, &evacuation, para);
+
+        Simulation {
+            map_album: map_album.clone(),
+            para: para.clone(),
+            fire,
+            smoke,
+            stairwells,
+            heat,
+            doors,
+            alarm,
+            suppression,
+            compartments,
+            pass,
+            evacuation,
+            elevators,
+            occupancy,
+            rescue: BuildingRescue::default(),
+            crowd,
+            rng,
+            time: 0.0,
+            evacuation_time: 0.0,
+            next_reroute: 0.0,
+        }
+    }
+
+    //推进dt秒，返回这一步中发生的事件
+    pub fn step(&mut self, dt: f32) -> Vec<SimEvent> {
+        self.time += dt;
+        self.fire.ignition(dt, &mut self.rng.rng);
+        self.fire
+            .burning(&mut self.smoke, self.para.s, Duration::from_secs_f32(dt));
+        self.suppression.update(
+            dt,
+            self.time,
+            &mut self.fire,
+            &self.smoke,
+            &self.para,
+            &mut self.rng.rng,
+        );
+        self.heat.update(&self.fire, &self.para, dt);
+        //高温代价每一步都更新，任何时候重算疏散场都用的是当前的气温
+        self.pass.update_from_heat(&self.heat, &self.para);
+        self.doors.expose_to_fire(dt, &self.fire, &self.para);
+        self.doors.tick(dt, &mut self.fire, &mut self.smoke);
+        self.smoke.diffuse(dt, &self.para);
+        self.stairwells.transfer(&mut self.smoke, dt);
+        self.stairwells.record(&self.smoke, &self.para, self.time);
+        //警报响起的楼层防火门关闭
+        for storey in self
+            .alarm
+            .check(&self.fire, &self.smoke, &self.para, self.time)
+        {
+            self.doors.release_fire_doors(&storey);
+        }
+        self.compartment_check();
+        self.elevator_lockout();
+        if self.para.reroute_interval > 0.0 && self.time >= self.next_reroute {
+            self.next_reroute = self.time + self.para.reroute_interval;
+            self.reroute();
+        }
+        let mut events = self.elevators_run(dt);
+        let alarm = &self.alarm;
+        let time = self.time;
+        let started = |human: &Human| alarm.is_started(&human.storey, human.pre_movement, time);
+        self.rescue.update(
+            &mut self.crowd,
+            &self.pass,
+            &self.evacuation,
+            &self.para,
+            time,
+            started,
+        );
+        self.crowd.pick_up_incapacitated(started);
+        events.extend(self.people_run(dt));
+        events.extend(self.carried_follow());
+        events
+    }
+
+    //所有人都已死亡或疏散时模拟结束，就地避难时待在避难区里的人也算结束
+    pub fn is_finished(&self) -> bool {
+        let refuge = self.para.horizontal_evacuation == HorizontalMode::Refuge;
+        self.crowd.humans.iter().all(|human| {
+            human.is_dead || human.is_evacuated || (refuge && human.refuge_at.is_some())
+        })
+    }
+
+    pub fn in_refuge_num(&self) -> usize {
+        self.crowd
+            .humans
+            .iter()
+            .filter(|x| !x.is_dead && x.refuge_at.is_some())
+            .count()
+    }
+
+    pub fn dead_num(&self) -> usize {
+        self.crowd.humans.iter().filter(|x| x.is_dead).count()
+    }
+
+    pub fn evacuated_num(&self) -> usize {
+        self.crowd.humans.iter().filter(|x| x.is_evacuated).count()
+    }
+
+    pub fn sheltered_num(&self) -> usize {
+        self.crowd
+            .humans
+            .iter()
+            .filter(|x| x.is_evacuated && x.is_safe)
+            .count()
+    }
+
+    //以固定步长一直推进到结束或超过max_time
+    pub fn run_to_end(&mut self, time_step: f32, max_time: f32) {
+        while !self.is_finished() && self.time < max_time {
+            self.step(time_step);
+        }
+    }
+
+    pub fn result(&self) -> SimResult {
+        let dead = self.dead_num();
+        let evacuated = self.evacuated_num();
+        let population = self.crowd.humans.len();
+        SimResult {
+            seed: self.rng.seed,
+            population,
+            dead,
+            evacuated,
+            sheltered: self.sheltered_num(),
+            trapped: self
+                .crowd
+                .humans
+                .iter()
+                .filter(|x| !x.is_dead && !x.is_evacuated && x.refuge_at.is_none())
+                .count(),
+            evacuation_time: self.evacuation_time,
+            end_time: self.time,
+            alarm_time: self.alarm.first_alarm(),
+            sprinklers_activated: self.suppression.activated_num(),
+            fires_extinguished: self.suppression.extinguished_num(),
+            doors_failed: self.doors.failed_num(),
+            peak_temperature: self.heat.peak,
+            smoke: self.smoke.balance(),
+            stairwells: self.stairwells.outcomes(),
+            doses: self.crowd.doses(),
+            elevators: self.elevators.usage(),
+            flows: self.occupancy.flows(),
+            rescues: self.rescue.records.clone(),
+            profiles: self.crowd.profile_outcomes(),
+            in_refuge: self.in_refuge_num(),
+            compartments: self.compartments.outcomes(&self.crowd.humans),
+        }
+    }
+
+    //按当前的烟雾和气温重新计算疏散场，还在楼内走动的人沿新的疏散场走
+    fn reroute(&mut self) {
+        self.pass.update_from_smoke(&self.smoke);
+        self.evacuation
+            .update_from_pass(&self.pass, self.para.smoke_route_weight);
+        self.evacuation
+            .update_queues(&self.crowd.humans, self.para.queue_radius);
+        for human in self.crowd.humans.iter_mut() {
+            if human.is_dead
+                || human.is_evacuated
+                || human.riding
+                || human.elevator.is_some()
+                || human.refuge_at.is_some()
+            {
+                continue;
+            }
+            //正去背病人的员工不改道
+            if human.is_rescuing() && !matches!(human.behaviour, Behaviour::Carrying(_)) {
+                continue;
+            }
+            human.reroute(&self.evacuation, &self.para);
+        }
+    }
+
+    //有分区失去安全时从通行图中去掉它的避难格，正走向避难区的人改去别的避难区或出口
+    fn compartment_check(&mut self) {
+        if !self
+            .compartments
+            .update(&self.fire, &self.smoke, &self.para, self.time)
+        {
+            return;
+        }
+        self.compartments.update_pass(&mut self.pass);
+        self.evacuation
+            .update_from_pass(&self.pass, self.para.smoke_route_weight);
+        for human in self.crowd.humans.iter_mut() {
+            if human.is_dead
+                || human.riding
+                || human.elevator.is_some()
+                || human.refuge_at.is_some()
+                || !human.seeks_refuge(&self.para)
+            {
+                continue;
+            }
+            if human.is_rescuing() && !matches!(human.behaviour, Behaviour::Carrying(_)) {
+                continue;
+            }
+            human.reroute(&self.evacuation, &self.para);
+        }
+    }
+
+    //电梯锁定后从通行图中去掉它的停靠格，正在等候或走向它的人改走楼梯
+    fn elevator_lockout(&mut self) {
+        let locked = self.elevators.lockout(
+            &self.fire,
+            &self.smoke,
+            self.para.elevator_smoke_limit,
+            self.time,
+        );
+        for &id in locked.iter() {
+            for stop in self.elevators.elevators[id].stops.iter() {
+                let storey_pass = self.pass.maps.get_mut(&stop.storey).unwrap();
+                storey_pass.elevator.retain(|&tile| tile != stop.tile);
+            }
+        }
+        if locked.is_empty() {
+            return;
+        }
+        self.evacuation
+            .update_from_pass(&self.pass, self.para.smoke_route_weight);
+        for id in locked {
+            for human in self.crowd.humans.iter_mut() {
+                if human.is_dead || human.is_evacuated || human.riding {
+                    continue;
+                }
+                let heading =
+                    self.map_album.elevator_at(&human.storey, human.target_tile) == Some(id);
+                if human.elevator == Some(id) || heading {
+                    human.elevator = None;
+                    human.plan_to_exit(
+                        &self.pass.maps[&human.storey],
+                        &self.evacuation,
+                        &self.para,
+                    );
+                }
+            }
+        }
+    }
+
+    //电梯运行，走出轿厢的人在所到楼层继续寻找出口
+    fn elevators_run(&mut self, dt: f32) -> Vec<SimEvent> {
+        let mut events = Vec::new();
+        for (id, stop) in self.elevators.run(dt, &self.para, &mut self.crowd.humans) {
+            let human = &mut self.crowd.humans[id];
+            human.riding = false;
+            human.elevator = None;
+            human.storey = stop.storey;
+            human.now_tile = stop.tile;
+            human.plan_to_exit(&self.pass.maps[&human.storey], &self.evacuation, &self.para);
+            events.push(SimEvent::ChangeStorey(id));
+        }
+        events
+    }
+
+    fn people_run(&mut self, dt: f32) -> Vec<SimEvent> {
+        let mut events = Vec::new();
+        let mut delivered = Vec::new();
+        self.occupancy.count(&self.crowd.humans);
+        for human in self.crowd.humans.iter_mut() {
+            //轿厢里的人不受烟雾影响，由电梯带着走
+            if human.is_dead || human.is_safe || human.riding {
+                continue;
+            }
+            let id = human.id;
+            let storey_smoke = &self.smoke.maps[&human.storey];
+
+            let storey_heat = &self.heat.maps[&human.storey];
+            match self.para.toxicity {
+                ToxicityModel::Simple => {
+                    human.smoke_damage(storey_smoke, dt);
+                    if self.para.heat_damage {
+                        human.heat_damage(storey_heat, &self.para, dt);
+                    }
+                }
+                ToxicityModel::Fed => {
+                    human.fed_damage(storey_smoke, storey_heat, &self.para, dt, self.time)
+                }
+            }
+
+            if human.hp <= 0.0 {
+                human.is_dead = true;
+                events.push(SimEvent::Dead(id));
+                continue;
+            }
+
+            let (i, j) = human.now_tile;
+            human.update_behaviour(storey_smoke.map[i][j].get_density(), &self.para);
+
+            //在避难区里的人：分区仍安全、也还没到放行的时间就待着，否则离开避难区
+            let storey_pass = &self.pass.maps[&human.storey];
+            if let Some(since) = human.refuge_at {
+                let safe = storey_pass.refuge.contains(&human.now_tile);
+                let dwell_over = self.para.horizontal_evacuation == HorizontalMode::Staged
+                    && self.time - since >= self.para.refuge_dwell;
+                if safe && !dwell_over {
+                    continue;
+                }
+                human.refuge_at = None;
+                human.released |= dwell_over;
+                if !human.is_incapacitated() {
+                    human.behaviour = Behaviour::Normal;
+                    human.plan_to_exit(storey_pass, &self.evacuation, &self.para);
+                }
+            }
+
+            //失去行动能力的人留在原地，或者被别人背着走；倒在避难区里的人就地避难
+            if human.is_incapacitated() || human.carried_by.is_some() {
+                if human.carried_by.is_none()
+                    && human.refuge_at.is_none()
+                    && human.seeks_refuge(&self.para)
+                    && storey_pass.refuge.contains(&human.now_tile)
+                {
+                    human.refuge_at = Some(self.time);
+                }
+                continue;
+            }
+
+            //警报响起并过了反应时间才开始疏散，惊慌的人不等
+            if human.behaviour != Behaviour::Panicked
+                && !self
+                    .alarm
+                    .is_started(&human.storey, human.pre_movement, self.time)
+            {
+                continue;
+            }
+
+            //在停靠格等电梯
+            if human.elevator.is_some() {
+                continue;
+            }
+
+            //走到避难区的人留下避难，这一格已挤满时去同层还有空位的避难格；背着人的人把他一起放下
+            let waiting = human.is_rescuing() && !matches!(human.behaviour, Behaviour::Carrying(_));
+            if human.seeks_refuge(&self.para)
+                && !waiting
+                && human.my_path.is_empty()
+                && human.position == human.next_position
+                && storey_pass.refuge.contains(&human.now_tile)
+            {
+                match roomier_refuge(
+                    storey_pass,
+                    &self.occupancy,
+                    &human.storey,
+                    human.now_tile,
+                    self.para.tile_capacity,
+                ) {
+                    Some(tile) if human.head_to(tile, storey_pass) => (),
+                    _ => {
+                        human.refuge_at = Some(self.time);
+                        match human.behaviour {
+                            Behaviour::Carrying(patient) => delivered.push((id, patient, true)),
+                            _ => human.behaviour = Behaviour::Sheltering,
+                        }
+                        continue;
+                    }
+                }
+            }
+
+            //没有路可走时被困，重新规划出路径后恢复；在病人身边等人到齐的员工不算
+            if !human.is_evacuated && !human.is_rescuing() {
+                let stuck = human.my_path.is_empty() && human.position == human.next_position;
+                match human.behaviour {
+                    Behaviour::Normal | Behaviour::Panicked if stuck => {
+                        human.behaviour = Behaviour::Trapped
+                    }
+                    Behaviour::Trapped if !stuck => human.behaviour = Behaviour::Normal,
+                    _ => (),
+                }
+            }
+
+            if human.position == human.next_position {
+                //在门前等候时next_tile就是now_tile，路径不出队
+                let arrived = if human.next_tile != human.now_tile {
+                    human.my_path.pop_front()
+                } else {
+                    None
+                };
+                if let Some(p) = arrived {
+                    human.now_tile = p;
+                    if human.now_tile == human.target_tile {
+                        //员工走到病人身边，等派去的人到齐后背起
+                        if human.is_rescuing() && !matches!(human.behaviour, Behaviour::Carrying(_))
+                        {
+                            continue;
+                        }
+                        //走到避难区，下一步留下避难
+                        if human.seeks_refuge(&self.para)
+                            && self.pass.maps[&human.storey]
+                                .refuge
+                                .contains(&human.now_tile)
+                        {
+                            continue;
+                        }
+                        if !human.is_evacuated && human.use_elevator {
+                            if let Some(elevator_id) =
+                                self.map_album.elevator_at(&human.storey, human.now_tile)
+                            {
+                                let elevator = &mut self.elevators.elevators[elevator_id];
+                                match elevator.stop_of(&human.storey) {
+                                    Some(stop) if !elevator.is_locked() => {
+                                        human.elevator = Some(elevator_id);
+                                        elevator.call(stop);
+                                    }
+                                    _ => human.plan_to_exit(
+                                        &self.pass.maps[&human.storey],
+                                        &self.evacuation,
+                                        &self.para,
+                                    ),
+                                }
+                                continue;
+                            }
+                        }
+                        if !human.is_evacuated {
+                            if self
+                                .map_album
+                                .stair_toward_ground(&human.storey, human.now_tile)
+                                .is_some()
+                            {
+                                //连续的楼梯井一直走下去，直到落脚的楼梯格不再通向更接近首层的楼层
+                                for _ in 0..self.map_album.maps.len() {
+                                    match self
+                                        .map_album
+                                        .stair_toward_ground(&human.storey, human.now_tile)
+                                    {
+                                        Some(below) => {
+                                            human.storey = below.storey.clone();
+                                            human.now_tile = below.tile;
+                                        }
+                                        None => break,
+                                    }
+                                }
+                                human.plan_to_exit(
+                                    &self.pass.maps[&human.storey],
+                                    &self.evacuation,
+                                    &self.para,
+                                );
+                                events.push(SimEvent::ChangeStorey(id));
+                                continue;
+                            } else {
+                                //背着病人的员工到出口后把病人交出去，自己暂不疏散
+                                if let (Role::Staff, Behaviour::Carrying(patient)) =
+                                    (human.role, human.behaviour)
+                                {
+                                    delivered.push((id, patient, false));
+                                    continue;
+                                }
+                                self.evacuation_time = self.time;
+                                events.extend(evacuate(
+                                    human,
+                                    &self.map_album,
+                                    &mut self.occupancy,
+                                    self.time,
+                                ));
+                                continue;
+                            }
+                        } else {
+                            human.is_safe = true;
+                            events.push(SimEvent::Safe(id));
+                            continue;
+                        }
+                    }
+                }
+
+                if let Some(&next_tile) = human.my_path.front() {
+                    //下一格已满、门关着或通行能力已用完时在原地等候
+                    //已疏散的人在楼外走向安全区，不占格子
+                    let full = !human.is_evacuated
+                        && next_tile != human.now_tile
+                        && self.occupancy.is_full(
+                            &human.storey,
+                            next_tile,
+                            self.para.tile_capacity,
+                        );
+                    if !full && self.doors.try_pass(&human.storey, next_tile, &self.para) {
+                        if !human.is_evacuated {
+                            self.occupancy.leave(&human.storey, human.now_tile);
+                            self.occupancy.enter(&human.storey, next_tile);
+                            self.occupancy.record_pass(
+                                &self.map_album,
+                                &human.storey,
+                                next_tile,
+                                self.time,
+                            );
+                        }
+                        human.next_tile = next_tile;
+                    } else {
+                        human.next_tile = human.now_tile;
+                    }
+                    human.position = human.next_position;
+                    let storey = &self.pass.maps[&human.storey];
+                    let (rows, cols) = (storey.map.len() as f32, storey.map[0].len() as f32);
+                    human.clc_next_position(1.0, cols, rows);
+                    human.change_my_direction();
+                }
+            }
+
+            //已疏散的人在楼外，只受血量影响
+            let factor = if human.is_evacuated {
+                1.0
+            } else {
+                let tiles = &self.map_album.maps[&human.storey].tiles;
+                let on_stair = [human.now_tile, human.next_tile]
+                    .iter()
+                    .any(|&(i, j)| tiles[i][j] == TileType::Stair);
+                let stair = match on_stair {
+                    true if self.map_album.is_below_ground(&human.storey) => Some(StairWay::Up),
+                    true => Some(StairWay::Down),
+                    false => None,
+                };
+                let density =
+                    self.occupancy
+                        .density(&human.storey, human.now_tile, self.para.tile_area);
+                let (i, j) = human.now_tile;
+                let smoke = self.smoke.maps[&human.storey].map[i][j].get_density();
+                speed_factor(density, stair, smoke, &self.para)
+            };
+            human.change_my_speed(factor * human.behaviour_speed(&self.para));
+            human.walk(dt);
+        }
+        let mut plan = RescuePlan::new(&self.crowd);
+        for (staff, patient, refuge) in delivered {
+            //顺手背人的人把他放在避难区里一起避难
+            if self.crowd.humans[staff].role == Role::Occupant {
+                self.crowd.humans[patient].carried_by = None;
+                self.crowd.humans[patient].refuge_at = Some(self.time);
+                self.crowd.humans[staff].behaviour = Behaviour::Sheltering;
+                continue;
+            }
+            if self
+                .rescue
+                .deliver(&mut self.crowd, staff, patient, self.time, refuge)
+                && !refuge
+            {
+                let human = &self.crowd.humans[patient];
+                self.occupancy
+                    .record_exit(&human.storey, human.now_tile, self.time);
+                self.evacuation_time = self.time;
+                events.push(SimEvent::Evacuated(patient));
+                events.push(SimEvent::Safe(patient));
+            }
+            //还有病人要背就回去，否则自己也疏散出去，或者留在避难区里
+            if !self.rescue.assign(
+                &mut self.crowd,
+                staff,
+                &self.pass,
+                &self.para,
+                self.time,
+                &mut plan,
+            ) {
+                if refuge {
+                    self.crowd.humans[staff].behaviour = Behaviour::Sheltering;
+                    continue;
+                }
+                self.evacuation_time = self.time;
+                events.extend(evacuate(
+                    &mut self.crowd.humans[staff],
+                    &self.map_album,
+                    &mut self.occupancy,
+                    self.time,
+                ));
+            }
+        }
+        events
+    }
+
+    //被背着的人跟着走，背他的人疏散出去时一起疏散并送到安全处
+    fn carried_follow(&mut self) -> Vec<SimEvent> {
+        let mut events = Vec::new();
+        for id in 0..self.crowd.humans.len() {
+            let Some(carrier) = self.crowd.humans[id].carried_by else {
+                continue;
+            };
+            if !self.crowd.humans[carrier].is_evacuated {
+                continue;
+            }
+            let human = &mut self.crowd.humans[id];
+            human.carried_by = None;
+            human.is_evacuated = true;
+            human.is_safe = true;
+            human.behaviour = Behaviour::Sheltering;
+            self.evacuation_time = self.time;
+            events.push(SimEvent::Evacuated(id));
+            events.push(SimEvent::Safe(id));
+            self.crowd.humans[carrier].behaviour = Behaviour::Sheltering;
+        }
+        let changed = self.crowd.follow_carriers();
+        events.extend(changed.into_iter().map(SimEvent::ChangeStorey));
+        events
+    }
+}
+
+//避难格tile已挤满时，同层离它最近、走进去后还留有空位的避难格；都没有空位时返回None
+fn roomier_refuge(
+    pass: &StoreyPass,
+    occupancy: &BuildingOccupancy,
+    storey: &MapMarker,
+    tile: (usize, usize),
+    capacity: usize,
+) -> Option<(usize, usize)> {
+    if !occupancy.is_full(storey, tile, capacity) {
+        return None;
+    }
+    pass.refuge
+        .iter()
+        .filter(|&&x| !occupancy.is_full(storey, x, capacity.saturating_sub(1)))
+        .min_by_key(|&&(i, j)| i.abs_diff(tile.0) + j.abs_diff(tile.1))
+        .copied()
+}
+
+//走到出口疏散出去，再去找楼外的安全区
+fn evacuate(
+    human: &mut Human,
+    map_album: &MapAlbum,
+    occupancy: &mut BuildingOccupancy,
+    time: f32,
+) -> Vec<SimEvent> {
+    let mut events = vec![SimEvent::Evacuated(human.id)];
+    human.is_evacuated = true;
+    human.behaviour = Behaviour::Sheltering;
+    occupancy.leave(&human.storey, human.now_tile);
+    occupancy.record_exit(&human.storey, human.now_tile, time);
+    human.plan_to_safe_place(&map_album.maps[&human.storey]);
+    if human.my_path.is_empty() {
+        human.is_safe = true;
+        events.push(SimEvent::Safe(human.id));
+    }
+    events
+}
@@ -1,12 +1,32 @@
+pub mod alarm;
+pub mod behaviour;
+pub mod compartment;
+pub mod door;
+pub mod elevator;
 pub mod fire;
+pub mod heat;
 pub mod human;
+pub mod occupancy;
+pub mod profile;
+pub mod rescue;
 pub mod smoke;
+pub mod speed;
+pub mod stairwell;
+pub mod suppression;
+pub mod toxicity;
 
 use bevy::prelude::*;
 
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use serde_json;
 
+use self::alarm::AlarmScope;
+use self::compartment::HorizontalMode;
+use self::speed::FundamentalDiagram;
+use self::toxicity::ToxicityModel;
+
 #[derive(Resource, Debug, Clone, Serialize, Deserialize)]
 pub struct NeededParameters {
     pub k: f32,            //引燃系数
@@ -18,6 +38,372 @@ pub struct NeededParameters {
     pub h: f32,            //人员初始生命值随机算子
     pub v: f32,            //人员移动速度随机算子
     pub c: f32,            //可燃物替换随机算子
+    #[serde(default)]
+    pub seed: Option<u64>, //随机种子，不填时随机生成
+    #[serde(default)]
+    pub elevator_ratio: f32, //选择乘电梯疏散的人员比例
+    #[serde(default = "default_elevator_capacity")]
+    pub elevator_capacity: usize, //电梯轿厢容量（人）
+    #[serde(default = "default_elevator_door_time")]
+    pub elevator_door_time: f32, //每次停靠开关门时间（秒）
+    #[serde(default = "default_elevator_speed")]
+    pub elevator_speed: f32, //电梯运行速度（米/秒）
+    #[serde(default = "default_elevator_smoke_limit")]
+    pub elevator_smoke_limit: f32, //停靠格烟雾浓度达到该值时电梯进入消防锁定
+    #[serde(default = "default_door_flow")]
+    pub door_flow: f32, //门的默认通行能力（人/秒）
+    #[serde(default = "default_door_open_time")]
+    pub door_open_time: f32, //推开一扇关着的门所需时间（秒）
+    #[serde(default = "default_door_close_time")]
+    pub door_close_time: f32, //自闭门无人通过后自动关上的时间（秒）
+    #[serde(default = "default_detector_threshold")]
+    pub detector_threshold: f32, //探测器报警的烟雾浓度
+    #[serde(default = "default_call_point_radius")]
+    pub call_point_radius: usize, //报警按钮周围多少格内着火时会被按下
+    #[serde(default)]
+    pub alarm_scope: AlarmScope, //警报只响本层（Storey）还是整栋楼（Building）
+    #[serde(default)]
+    pub pre_movement: f32, //警报响起后人员开始疏散前的平均反应时间（秒）
+    #[serde(default = "default_pre_movement_spread")]
+    pub pre_movement_spread: f32, //反应时间随机算子(0~1)
+    #[serde(default = "default_suppression")]
+    pub suppression: bool, //是否启用喷淋（消火栓格）灭火
+    #[serde(default = "default_sprinkler_smoke_threshold")]
+    pub sprinkler_smoke_threshold: f32, //喷头所在格烟雾浓度达到该值时触发
+    #[serde(default = "default_sprinkler_heat_radius")]
+    pub sprinkler_heat_radius: usize, //喷头周围多少格内着火时受热触发
+    #[serde(default = "default_sprinkler_delay")]
+    pub sprinkler_delay: f32, //触发后开始喷水的延迟（秒）
+    #[serde(default = "default_sprinkler_radius")]
+    pub sprinkler_radius: f32, //喷水的有效半径（格）
+    #[serde(default = "default_sprinkler_extinguish_rate")]
+    pub sprinkler_extinguish_rate: f32, //有效范围内燃烧格每秒被扑灭的概率强度
+    #[serde(default = "default_sprinkler_smoke_cut")]
+    pub sprinkler_smoke_cut: f32, //有效范围内燃烧格产烟量的削减比例(0~1)
+    #[serde(default = "default_reroute_interval")]
+    pub reroute_interval: f32, //按烟雾重新规划路线的间隔（秒），不大于0时不重新规划
+    #[serde(default = "default_smoke_route_weight")]
+    pub smoke_route_weight: f32, //规划路线时烟雾浓度折算成的额外距离（格/单位浓度）
+    #[serde(default = "default_queue_weight")]
+    pub queue_weight: f32, //选择出口时每个排队的人折算成的额外距离（格）
+    #[serde(default = "default_queue_radius")]
+    pub queue_radius: usize, //离出口多少格以内走向它的人算作排队
+    #[serde(default = "default_route_switch_cost")]
+    pub route_switch_cost: f32, //改选另一个出口的额外距离（格），避免来回改变主意
+    #[serde(default)]
+    pub visitor_ratio: f32, //只知道主出口的访客比例
+    #[serde(default = "default_tile_capacity")]
+    pub tile_capacity: usize, //一格最多同时容纳的人数，格满时后面的人排队等候
+    #[serde(default)]
+    pub fundamental_diagram: FundamentalDiagram, //步行速度随人群密度变化的关系
+    #[serde(default = "default_tile_area")]
+    pub tile_area: f32, //一格的面积（平方米），用于计算人群密度
+    #[serde(default = "default_jam_density")]
+    pub jam_density: f32, //速度降为0时的密度（人/平方米）
+    #[serde(default = "default_weidmann_gamma")]
+    pub weidmann_gamma: f32, //Weidmann基本图的形状参数
+    #[serde(default = "default_stair_up_factor")]
+    pub stair_up_factor: f32, //上楼梯时的速度系数
+    #[serde(default = "default_stair_down_factor")]
+    pub stair_down_factor: f32, //下楼梯时的速度系数
+    #[serde(default = "default_smoke_speed_reduction")]
+    pub smoke_speed_reduction: f32, //烟雾浓度每增加1速度降低的比例
+    #[serde(default = "default_min_speed_factor")]
+    pub min_speed_factor: f32, //速度系数的下限
+    #[serde(default = "default_panic_smoke_threshold")]
+    pub panic_smoke_threshold: f32, //所在格烟雾浓度达到它时惊慌
+    #[serde(default = "default_panic_hp_ratio")]
+    pub panic_hp_ratio: f32, //血量低于最大血量的这一比例时惊慌
+    #[serde(default = "default_panic_speed_factor")]
+    pub panic_speed_factor: f32, //惊慌时的速度系数
+    #[serde(default = "default_incapacitated_hp_ratio")]
+    pub incapacitated_hp_ratio: f32, //血量低于最大血量的这一比例时失去行动能力
+    #[serde(default = "default_carry_speed_factor")]
+    pub carry_speed_factor: f32, //背着人时的速度系数
+    #[serde(default)]
+    pub staff_ratio: f32, //员工比例
+    #[serde(default)]
+    pub bedridden_ratio: f32, //卧床病人比例
+    #[serde(default = "default_staff_per_patient")]
+    pub staff_per_patient: usize, //背一个病人需要的员工人数
+    #[serde(default)]
+    pub profiles: String, //人员类型配置文件名（./assets/parameters/下），为空时不区分人员类型
+    #[serde(default)]
+    pub horizontal_evacuation: HorizontalMode, //是否先水平疏散到同层安全的防烟分区
+    #[serde(default = "default_refuge_dwell")]
+    pub refuge_dwell: f32, //分阶段疏散时在避难区停留多久后继续向出口疏散（秒）
+    #[serde(default = "default_compartment_smoke_limit")]
+    pub compartment_smoke_limit: f32, //分区内烟雾浓度达到该值时不再安全
+    #[serde(default = "default_ignition_delay")]
+    pub ignition_delay: f32, //被火势波及的可燃物平均多久被引燃（秒），不大于0时立即引燃
+    #[serde(default = "default_door_fire_resistance")]
+    pub door_fire_resistance: f32, //关着的门被火势波及多久后烧穿（秒）
+    #[serde(default = "default_ambient_temperature")]
+    pub ambient_temperature: f32, //环境温度（°C）
+    #[serde(default = "default_flame_temperature")]
+    pub flame_temperature: f32, //燃烧格的气温（°C）
+    #[serde(default = "default_heat_radius")]
+    pub heat_radius: usize, //燃烧格向外传热的范围（格）
+    #[serde(default = "default_heat_time_constant")]
+    pub heat_time_constant: f32, //气温趋向目标温度的时间常数（秒）
+    #[serde(default = "default_flame_radiant_flux")]
+    pub flame_radiant_flux: f32, //燃烧格在1格距离处的辐射热通量（kW/m²）
+    #[serde(default = "default_heat_route_weight")]
+    pub heat_route_weight: f32, //规划路线时每升温100°C折算成的额外距离（格）
+    #[serde(default = "default_heat_damage")]
+    pub heat_damage: bool, //是否计算高温和热辐射对人的伤害
+    #[serde(default = "default_smoke_diffusion")]
+    pub smoke_diffusion: f32, //烟雾扩散系数（格²/秒）
+    #[serde(default)]
+    pub smoke_drift: [f32; 2], //烟雾随气流平移的速度（格/秒，分别沿行和列的方向）
+    #[serde(default = "default_stair_smoke_rate")]
+    pub stair_smoke_rate: f32, //楼梯下端格的烟每秒沿楼梯升到上层的比例
+    #[serde(default = "default_shaft_smoke_rate")]
+    pub shaft_smoke_rate: f32, //电梯井和竖井开口格的烟每秒升到上一层开口的比例
+    #[serde(default = "default_stair_smoke_limit")]
+    pub stair_smoke_limit: f32, //梯间烟雾浓度达到该值时记为被烟污染
+    #[serde(default = "default_stairwell_sample_interval")]
+    pub stairwell_sample_interval: f32, //记录梯间烟雾的时间间隔（秒）
+    #[serde(default)]
+    pub toxicity: ToxicityModel, //烟气伤害模型
+    #[serde(default = "default_co_yield")]
+    pub co_yield: f32, //每单位烟雾浓度含的CO（ppm）
+    #[serde(default = "default_co2_yield")]
+    pub co2_yield: f32, //每单位烟雾浓度含的CO2（%）
+    #[serde(default = "default_o2_depletion")]
+    pub o2_depletion: f32, //每单位烟雾浓度使O2降低的量（%）
+    #[serde(default = "default_fed_incapacitation")]
+    pub fed_incapacitation: f32, //总剂量达到它时失去行动能力
+    #[serde(default = "default_fed_death")]
+    pub fed_death: f32, //总剂量达到它时死亡
+}
+
+fn default_elevator_capacity() -> usize {
+    10
+}
+
+fn default_elevator_door_time() -> f32 {
+    6.0
+}
+
+fn default_elevator_speed() -> f32 {
+    1.0
+}
+
+fn default_elevator_smoke_limit() -> f32 {
+    1.0
+}
+
+fn default_door_flow() -> f32 {
+    1.3
+}
+
+fn default_door_open_time() -> f32 {
+    2.0
+}
+
+fn default_door_close_time() -> f32 {
+    5.0
+}
+
+fn default_detector_threshold() -> f32 {
+    0.1
+}
+
+fn default_call_point_radius() -> usize {
+    2
+}
+
+fn default_pre_movement_spread() -> f32 {
+    0.5
+}
+
+fn default_suppression() -> bool {
+    false
+}
+
+fn default_sprinkler_smoke_threshold() -> f32 {
+    0.5
+}
+
+fn default_sprinkler_heat_radius() -> usize {
+    1
+}
+
+fn default_sprinkler_delay() -> f32 {
+    10.0
+}
+
+fn default_sprinkler_radius() -> f32 {
+    3.0
+}
+
+fn default_sprinkler_extinguish_rate() -> f32 {
+    0.1
+}
+
+fn default_sprinkler_smoke_cut() -> f32 {
+    0.7
+}
+
+fn default_reroute_interval() -> f32 {
+    2.0
+}
+
+fn default_smoke_route_weight() -> f32 {
+    10.0
+}
+
+fn default_queue_weight() -> f32 {
+    1.0
+}
+
+fn default_queue_radius() -> usize {
+    5
+}
+
+fn default_route_switch_cost() -> f32 {
+    2.0
+}
+
+fn default_tile_capacity() -> usize {
+    4
+}
+
+fn default_tile_area() -> f32 {
+    1.0
+}
+
+fn default_jam_density() -> f32 {
+    5.4
+}
+
+fn default_weidmann_gamma() -> f32 {
+    1.913
+}
+
+fn default_stair_up_factor() -> f32 {
+    0.5
+}
+
+fn default_stair_down_factor() -> f32 {
+    0.6
+}
+
+fn default_smoke_speed_reduction() -> f32 {
+    0.05
+}
+
+fn default_min_speed_factor() -> f32 {
+    0.1
+}
+
+fn default_panic_smoke_threshold() -> f32 {
+    5.0
+}
+
+fn default_panic_hp_ratio() -> f32 {
+    0.5
+}
+
+fn default_panic_speed_factor() -> f32 {
+    1.2
+}
+
+fn default_incapacitated_hp_ratio() -> f32 {
+    0.2
+}
+
+fn default_carry_speed_factor() -> f32 {
+    0.5
+}
+
+fn default_staff_per_patient() -> usize {
+    1
+}
+
+fn default_refuge_dwell() -> f32 {
+    300.0
+}
+
+fn default_compartment_smoke_limit() -> f32 {
+    1.0
+}
+
+fn default_ignition_delay() -> f32 {
+    1.0
+}
+
+fn default_door_fire_resistance() -> f32 {
+    1800.0
+}
+
+fn default_ambient_temperature() -> f32 {
+    20.0
+}
+
+fn default_flame_temperature() -> f32 {
+    800.0
+}
+
+fn default_heat_radius() -> usize {
+    3
+}
+
+fn default_heat_time_constant() -> f32 {
+    10.0
+}
+
+fn default_flame_radiant_flux() -> f32 {
+    20.0
+}
+
+fn default_heat_route_weight() -> f32 {
+    2.0
+}
+
+fn default_heat_damage() -> bool {
+    true
+}
+
+fn default_smoke_diffusion() -> f32 {
+    2.0
+}
+
+fn default_stair_smoke_rate() -> f32 {
+    0.2
+}
+
+fn default_shaft_smoke_rate() -> f32 {
+    0.1
+}
+
+fn default_stair_smoke_limit() -> f32 {
+    1.0
+}
+
+fn default_stairwell_sample_interval() -> f32 {
+    10.0
+}
+
+fn default_co_yield() -> f32 {
+    1000.0
+}
+
+fn default_co2_yield() -> f32 {
+    1.0
+}
+
+fn default_o2_depletion() -> f32 {
+    1.0
+}
+
+fn default_fed_incapacitation() -> f32 {
+    1.0
+}
+
+fn default_fed_death() -> f32 {
+    3.0
 }
 
 impl Default for NeededParameters {
@@ -32,6 +418,75 @@ impl Default for NeededParameters {
             h: 0.0,
             v: 0.0,
             c: 0.0,
+            seed: None,
+            elevator_ratio: 0.0,
+            elevator_capacity: default_elevator_capacity(),
+            elevator_door_time: default_elevator_door_time(),
+            elevator_speed: default_elevator_speed(),
+            elevator_smoke_limit: default_elevator_smoke_limit(),
+            door_flow: default_door_flow(),
+            door_open_time: default_door_open_time(),
+            door_close_time: default_door_close_time(),
+            detector_threshold: default_detector_threshold(),
+            call_point_radius: default_call_point_radius(),
+            alarm_scope: AlarmScope::default(),
+            pre_movement: 0.0,
+            pre_movement_spread: default_pre_movement_spread(),
+            suppression: default_suppression(),
+            sprinkler_smoke_threshold: default_sprinkler_smoke_threshold(),
+            sprinkler_heat_radius: default_sprinkler_heat_radius(),
+            sprinkler_delay: default_sprinkler_delay(),
+            sprinkler_radius: default_sprinkler_radius(),
+            sprinkler_extinguish_rate: default_sprinkler_extinguish_rate(),
+            sprinkler_smoke_cut: default_sprinkler_smoke_cut(),
+            reroute_interval: default_reroute_interval(),
+            smoke_route_weight: default_smoke_route_weight(),
+            queue_weight: default_queue_weight(),
+            queue_radius: default_queue_radius(),
+            route_switch_cost: default_route_switch_cost(),
+            visitor_ratio: 0.0,
+            tile_capacity: default_tile_capacity(),
+            fundamental_diagram: FundamentalDiagram::default(),
+            tile_area: default_tile_area(),
+            jam_density: default_jam_density(),
+            weidmann_gamma: default_weidmann_gamma(),
+            stair_up_factor: default_stair_up_factor(),
+            stair_down_factor: default_stair_down_factor(),
+            smoke_speed_reduction: default_smoke_speed_reduction(),
+            min_speed_factor: default_min_speed_factor(),
+            panic_smoke_threshold: default_panic_smoke_threshold(),
+            panic_hp_ratio: default_panic_hp_ratio(),
+            panic_speed_factor: default_panic_speed_factor(),
+            incapacitated_hp_ratio: default_incapacitated_hp_ratio(),
+            carry_speed_factor: default_carry_speed_factor(),
+            staff_ratio: 0.0,
+            bedridden_ratio: 0.0,
+            staff_per_patient: default_staff_per_patient(),
+            profiles: String::new(),
+            horizontal_evacuation: HorizontalMode::default(),
+            refuge_dwell: default_refuge_dwell(),
+            compartment_smoke_limit: default_compartment_smoke_limit(),
+            ignition_delay: default_ignition_delay(),
+            door_fire_resistance: default_door_fire_resistance(),
+            ambient_temperature: default_ambient_temperature(),
+            flame_temperature: default_flame_temperature(),
+            heat_radius: default_heat_radius(),
+            heat_time_constant: default_heat_time_constant(),
+            flame_radiant_flux: default_flame_radiant_flux(),
+            heat_route_weight: default_heat_route_weight(),
+            heat_damage: default_heat_damage(),
+            smoke_diffusion: default_smoke_diffusion(),
+            smoke_drift: [0.0; 2],
+            stair_smoke_rate: default_stair_smoke_rate(),
+            shaft_smoke_rate: default_shaft_smoke_rate(),
+            stair_smoke_limit: default_stair_smoke_limit(),
+            stairwell_sample_interval: default_stairwell_sample_interval(),
+            toxicity: ToxicityModel::default(),
+            co_yield: default_co_yield(),
+            co2_yield: default_co2_yield(),
+            o2_depletion: default_o2_depletion(),
+            fed_incapacitation: default_fed_incapacitation(),
+            fed_death: default_fed_death(),
         }
     }
 }
@@ -48,6 +503,72 @@ impl NeededParameters {
         let file = std::fs::File::open(path).unwrap();
         serde_json::from_reader(std::io::BufReader::new(file)).unwrap()
     }
+
+    //按字段名修改参数，字段名与json中的键相同
+    pub fn set_field(&mut self, name: &str, value: serde_json::Value) -> Result<(), String> {
+        let mut json = serde_json::to_value(&*self).map_err(|e| e.to_string())?;
+        let fields = json.as_object_mut().unwrap();
+        if !fields.contains_key(name) {
+            return Err(format!("unknown parameter: {}", name));
+        }
+        fields.insert(name.to_string(), value);
+        *self = serde_json::from_value(json).map_err(|e| format!("{}: {}", name, e))?;
+        Ok(())
+    }
+}
+
+//从命令行读取形如 --name <value> 的参数，没有这个参数时为None，缺少值或者值无法解析时返回错误
+pub fn value_from_args<T: std::str::FromStr>(name: &str) -> Result<Option<T>, String> {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(index) = args.iter().position(|arg| arg == name) else {
+        return Ok(None);
+    };
+    let value = args.get(index + 1).ok_or(format!("{} needs a value", name))?;
+    match value.parse() {
+        Ok(value) => Ok(Some(value)),
+        Err(_) => Err(format!("{}: invalid value {}", name, value)),
+    }
+}
+
+//从命令行读取所有形如 --name <value> 的参数，可以重复出现，最后一个参数缺少值时返回错误
+pub fn values_from_args(name: &str) -> Result<Vec<String>, String> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.last().is_some_and(|arg| arg == name) {
+        return Err(format!("{} needs a value", name));
+    }
+    Ok(args
+        .windows(2)
+        .filter(|pair| pair[0] == name)
+        .map(|pair| pair[1].clone())
+        .collect())
+}
+
+//从命令行读取随机种子：--seed <u64>
+pub fn seed_from_args() -> Result<Option<u64>, String> {
+    value_from_args("--seed")
+}
+
+//全局唯一的随机数发生器，所有随机过程都从这里取数，同一个种子可以完全复现一次模拟
+#[derive(Resource, Debug, Clone)]
+pub struct SimRng {
+    pub seed: u64,
+    pub rng: StdRng,
+}
+
+impl SimRng {
+    pub fn new(seed: Option<u64>) -> Self {
+        let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+        SimRng {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Default for SimRng {
+    fn default() -> Self {
+        SimRng::new(None)
+    }
 }
 
 pub fn get_needed_parameters(mut res: ResMut<NeededParameters>) {
@@ -105,5 +626,10 @@ pub fn get_needed_parameters(mut res: ResMut<NeededParameters>) {
     let c: f32 = c.trim().parse().unwrap();
     res.c = c;
 
+    println!("Please input the seed(请输入随机种子)(留空则随机):");
+    let mut seed = String::new();
+    std::io::stdin().read_line(&mut seed).unwrap();
+    res.seed = seed.trim().parse().ok();
+
     res.save("needed_parameters");
 }
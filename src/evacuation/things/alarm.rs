@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+use crate::components::painting::*;
+use crate::components::TileType;
+
+use super::fire::{BuildingFire, Fire};
+use super::smoke::BuildingSmoke;
+use super::NeededParameters;
+
+//警报范围：只响探测到火情的楼层，或整栋楼一起响
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlarmScope {
+    Storey,
+    #[default]
+    Building,
+}
+
+//探测器格在所在格烟雾浓度达到阈值时报警；报警按钮格在附近着火时被人按下
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorKind {
+    Detector,
+    CallPoint,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sensor {
+    pub kind: SensorKind,
+    pub storey: MapMarker,
+    pub tile: (usize, usize),
+    pub triggered_at: Option<f32>,
+}
+
+impl Sensor {
+    fn is_triggered(
+        &self,
+        fire: &BuildingFire,
+        smoke: &BuildingSmoke,
+        para: &NeededParameters,
+    ) -> bool {
+        let (i, j) = self.tile;
+        match self.kind {
+            SensorKind::Detector => {
+                smoke.maps[&self.storey].map[i][j].get_density() >= para.detector_threshold
+            }
+            SensorKind::CallPoint => {
+                let map = &fire.maps[&self.storey].map;
+                let r = para.call_point_radius;
+                (i.saturating_sub(r)..(i + r + 1).min(map.len())).any(|x| {
+                    (j.saturating_sub(r)..(j + r + 1).min(map[0].len()))
+                        .any(|y| matches!(map[x][y], Fire::On(_)))
+                })
+            }
+        }
+    }
+}
+
+//整栋楼的火灾报警：记录每一层警报响起的时间
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BuildingAlarm {
+    pub sensors: Vec<Sensor>,
+    pub storeys: HashMap<MapMarker, Option<f32>>,
+    pub scope: AlarmScope,
+}
+
+impl BuildingAlarm {
+    pub fn init_from_album(&mut self, map_album: &MapAlbum, scope: AlarmScope) {
+        self.scope = scope;
+        for marker in sorted_markers(&map_album.maps) {
+            let map = &map_album.maps[&marker];
+            for i in 0..map.tiles.len() {
+                for j in 0..map.tiles[0].len() {
+                    let kind = match map.tiles[i][j] {
+                        TileType::Detector => SensorKind::Detector,
+                        TileType::Alarm => SensorKind::CallPoint,
+                        _ => continue,
+                    };
+                    self.sensors.push(Sensor {
+                        kind,
+                        storey: marker.clone(),
+                        tile: (i, j),
+                        triggered_at: None,
+                    });
+                }
+            }
+            self.storeys.insert(marker, None);
+        }
+    }
+
+    //检查探测器和报警按钮，返回这一步警报新响起的楼层
+    //整栋楼没有探测器和报警按钮时，警报在开始时就响起
+    pub fn check(
+        &mut self,
+        fire: &BuildingFire,
+        smoke: &BuildingSmoke,
+        para: &NeededParameters,
+        time: f32,
+    ) -> Vec<MapMarker> {
+        let mut sources = Vec::new();
+        if self.sensors.is_empty() {
+            sources = self.storeys.keys().cloned().collect();
+        }
+        for sensor in self.sensors.iter_mut() {
+            if sensor.triggered_at.is_none() && sensor.is_triggered(fire, smoke, para) {
+                sensor.triggered_at = Some(time);
+                sources.push(sensor.storey.clone());
+            }
+        }
+        if self.scope == AlarmScope::Building && !sources.is_empty() {
+            sources = self.storeys.keys().cloned().collect();
+        }
+        let mut sounded = Vec::new();
+        for storey in sources {
+            let alarm = self.storeys.get_mut(&storey).unwrap();
+            if alarm.is_none() {
+                *alarm = Some(time);
+                sounded.push(storey);
+            }
+        }
+        sounded.sort();
+        sounded
+    }
+
+    pub fn alarm_time(&self, storey: &MapMarker) -> Option<f32> {
+        self.storeys.get(storey).copied().flatten()
+    }
+
+    //最早响起警报的时间
+    pub fn first_alarm(&self) -> Option<f32> {
+        self.storeys
+            .values()
+            .flatten()
+            .copied()
+            .min_by(|a, b| a.total_cmp(b))
+    }
+
+    //所在楼层的警报响起并过了反应时间后才开始疏散
+    pub fn is_started(&self, storey: &MapMarker, pre_movement: f32, time: f32) -> bool {
+        self.alarm_time(storey)
+            .is_some_and(|alarm| time >= alarm + pre_movement)
+    }
+}
@@ -0,0 +1,152 @@
+use super::human::{Human, TheCrowd};
+use super::rescue::Role;
+use super::toxicity::ToxicityModel;
+use super::NeededParameters;
+
+//人的行为状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Behaviour {
+    #[default]
+    Normal,
+    Panicked,        //烟雾浓或血量低时惊慌，走得快但不再排队
+    Incapacitated,   //血量过低或剂量达到失能阈值，不能自己行动
+    Carrying(usize), //正背着失去行动能力的人
+    Sheltering,      //已疏散，前往或待在安全区
+    Trapped,         //没有可达的出口
+}
+
+impl Human {
+    //按所在格的烟雾浓度和血量转换状态，被困由是否有可走的路径决定
+    //FED模型下失去行动能力只看剂量，不看血量
+    pub fn update_behaviour(&mut self, smoke: f32, para: &NeededParameters) {
+        if self.is_dead {
+            return;
+        }
+        if self.is_evacuated {
+            self.behaviour = Behaviour::Sheltering;
+            return;
+        }
+        let fed = para.toxicity == ToxicityModel::Fed;
+        let hp = self.hp / self.max_hp;
+        let incapacitated = match fed {
+            true => self.incapacitated_at.is_some(),
+            false => hp < para.incapacitated_hp_ratio,
+        };
+        if incapacitated {
+            self.behaviour = Behaviour::Incapacitated;
+            return;
+        }
+        let panic = smoke >= para.panic_smoke_threshold || (!fed && hp < para.panic_hp_ratio);
+        if self.behaviour == Behaviour::Normal && panic {
+            self.behaviour = Behaviour::Panicked;
+        }
+    }
+
+    pub fn is_incapacitated(&self) -> bool {
+        self.behaviour == Behaviour::Incapacitated
+    }
+
+    //要别人背着走的人：失去行动能力的人，和不能走楼梯、又没有可达的电梯而被困的人
+    pub fn needs_carrying(&self) -> bool {
+        self.is_incapacitated() || (self.behaviour == Behaviour::Trapped && !self.use_stairs)
+    }
+
+    //能顺手背起别人的人：正常行动、还在楼内且不在电梯里，员工另由派遣去救人
+    fn can_carry(&self) -> bool {
+        self.role == Role::Occupant
+            && self.behaviour == Behaviour::Normal
+            && !self.is_dead
+            && !self.is_evacuated
+            && !self.riding
+            && self.elevator.is_none()
+    }
+
+    //当前状态下的速度系数
+    pub fn behaviour_speed(&self, para: &NeededParameters) -> f32 {
+        match self.behaviour {
+            Behaviour::Panicked => para.panic_speed_factor,
+            Behaviour::Carrying(_) => para.carry_speed_factor,
+            Behaviour::Incapacitated => 0.0,
+            _ => 1.0,
+        }
+    }
+}
+
+impl TheCrowd {
+    //正常行动的人遇到同格或相邻格上没人背的要背着走的人时把他背起来
+    //started判断一个人是否已经开始疏散
+    pub fn pick_up_incapacitated(&mut self, started: impl Fn(&Human) -> bool) {
+        let waiting: Vec<usize> = self
+            .humans
+            .iter()
+            .filter(|x| {
+                x.needs_carrying()
+                    && x.carried_by.is_none()
+                    && x.refuge_at.is_none()
+                    && !self.humans.iter().any(|y| y.rescuing == Some(x.id))
+                    && !x.is_dead
+                    && !x.is_evacuated
+                    && !x.riding
+            })
+            .map(|x| x.id)
+            .collect();
+        for id in waiting {
+            let (storey, (i, j)) = (self.humans[id].storey.clone(), self.humans[id].now_tile);
+            let carrier = self.humans.iter().position(|x| {
+                let (x_i, x_j) = x.now_tile;
+                x.can_carry()
+                    && started(x)
+                    && x.storey == storey
+                    && x_i.abs_diff(i) + x_j.abs_diff(j) <= 1
+            });
+            if let Some(carrier) = carrier {
+                self.humans[carrier].behaviour = Behaviour::Carrying(id);
+                self.humans[id].carried_by = Some(carrier);
+            }
+        }
+    }
+
+    //被背着的人跟着背他的人走；背人的人倒下或被背的人死亡时放下
+    //返回换了楼层的被背者
+    pub fn follow_carriers(&mut self) -> Vec<usize> {
+        let mut changed = Vec::new();
+        for id in 0..self.humans.len() {
+            let Some(carrier) = self.humans[id].carried_by else {
+                continue;
+            };
+            let carrier_human = &self.humans[carrier];
+            if self.humans[id].is_dead
+                || carrier_human.is_dead
+                || carrier_human.behaviour != Behaviour::Carrying(id)
+            {
+                self.humans[id].carried_by = None;
+                if self.humans[carrier].behaviour == Behaviour::Carrying(id) {
+                    self.humans[carrier].behaviour = Behaviour::Normal;
+                }
+                continue;
+            }
+            let carrier_human = carrier_human.clone();
+            let human = &mut self.humans[id];
+            if human.storey != carrier_human.storey {
+                changed.push(id);
+            }
+            human.storey = carrier_human.storey;
+            human.now_tile = carrier_human.now_tile;
+            human.next_tile = carrier_human.now_tile;
+            human.position = carrier_human.position;
+            human.next_position = carrier_human.position;
+            human.riding = carrier_human.riding;
+        }
+        changed
+    }
+
+    //不同状态的人用不同的图片
+    pub fn texture_of(&self, human: &Human) -> String {
+        match human.behaviour {
+            Behaviour::Panicked => "./movethings/panicked_human.png".to_string(),
+            Behaviour::Incapacitated => "./movethings/incapacitated_human.png".to_string(),
+            Behaviour::Carrying(_) => "./movethings/carrying_human.png".to_string(),
+            _ => self.texture_path.clone(),
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::painting::*;
+use crate::components::TileType;
+
+use super::fire::{BuildingFire, Fire};
+use super::human::{BuildingPass, Human};
+use super::smoke::BuildingSmoke;
+use super::NeededParameters;
+
+//水平疏散方式：不水平疏散（Off），躲进同层安全分区的避难区就地避难（Refuge），
+//或先躲进避难区、停留一段时间后再继续向出口疏散（Staged）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HorizontalMode {
+    #[default]
+    Off,
+    Refuge,
+    Staged,
+}
+
+//一个防烟分区：分区内着火或烟雾达到上限后不再安全，之后也不再恢复
+#[derive(Debug, Clone, PartialEq)]
+pub struct Compartment {
+    pub storey: MapMarker,
+    pub name: String,
+    pub area: ((usize, usize), (usize, usize)),
+    pub refuges: Vec<(usize, usize)>,
+    pub unsafe_at: Option<f32>,
+}
+
+impl Compartment {
+    fn is_threatened(&self, fire: &BuildingFire, smoke: &BuildingSmoke, limit: f32) -> bool {
+        let ((i0, j0), (i1, j1)) = self.area;
+        let fire = &fire.maps[&self.storey].map;
+        let smoke = &smoke.maps[&self.storey].map;
+        (i0..=i1).any(|i| {
+            (j0..=j1)
+                .any(|j| matches!(fire[i][j], Fire::On(_)) || smoke[i][j].get_density() >= limit)
+        })
+    }
+
+    pub fn is_safe(&self) -> bool {
+        self.unsafe_at.is_none()
+    }
+}
+
+//每个分区结束时的状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompartmentOutcome {
+    pub storey: String,
+    pub name: String,
+    pub unsafe_at: Option<f32>, //不再安全的时间
+    pub sheltering: usize,      //结束时在它的避难区里的人数
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BuildingCompartments {
+    pub compartments: Vec<Compartment>,
+}
+
+impl BuildingCompartments {
+    //避难格不填时取分区内的安全区格，仍没有时取分区内所有地板格
+    pub fn init_from_album(&mut self, map_album: &MapAlbum) {
+        for spec in map_album.compartments.iter() {
+            let Some(map) = map_album.maps.get(&spec.storey) else {
+                continue;
+            };
+            let ((i0, j0), (i1, j1)) = spec.area;
+            let tiles_of = |kind: TileType| -> Vec<(usize, usize)> {
+                (i0..=i1)
+                    .flat_map(|i| (j0..=j1).map(move |j| (i, j)))
+                    .filter(|&(i, j)| map.tiles[i][j] == kind)
+                    .collect()
+            };
+            let mut refuges = spec.refuges.clone();
+            if refuges.is_empty() {
+                refuges = tiles_of(TileType::SavePlace);
+            }
+            if refuges.is_empty() {
+                refuges = tiles_of(TileType::Floor);
+            }
+            self.compartments.push(Compartment {
+                storey: spec.storey.clone(),
+                name: spec.name.clone(),
+                area: spec.area,
+                refuges,
+                unsafe_at: None,
+            });
+        }
+    }
+
+    //检查每个分区是否还安全，返回是否有分区刚刚失去安全
+    pub fn update(
+        &mut self,
+        fire: &BuildingFire,
+        smoke: &BuildingSmoke,
+        para: &NeededParameters,
+        time: f32,
+    ) -> bool {
+        let mut changed = false;
+        for compartment in self.compartments.iter_mut() {
+            if compartment.is_safe()
+                && compartment.is_threatened(fire, smoke, para.compartment_smoke_limit)
+            {
+                compartment.unsafe_at = Some(time);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    //把安全分区的避难格写进每一层的通行图，作为水平疏散的目标
+    pub fn update_pass(&self, building_pass: &mut BuildingPass) {
+        for (marker, storey_pass) in building_pass.maps.iter_mut() {
+            storey_pass.refuge = self
+                .compartments
+                .iter()
+                .filter(|x| &x.storey == marker && x.is_safe())
+                .flat_map(|x| x.refuges.iter().copied())
+                .collect();
+        }
+    }
+
+    pub fn outcomes(&self, humans: &[Human]) -> Vec<CompartmentOutcome> {
+        self.compartments
+            .iter()
+            .map(|compartment| CompartmentOutcome {
+                storey: compartment.storey.name.clone(),
+                name: compartment.name.clone(),
+                unsafe_at: compartment.unsafe_at,
+                sheltering: humans
+                    .iter()
+                    .filter(|x| {
+                        !x.is_dead
+                            && x.refuge_at.is_some()
+                            && x.storey == compartment.storey
+                            && compartment.refuges.contains(&x.now_tile)
+                    })
+                    .count(),
+            })
+            .collect()
+    }
+}
+
+impl Human {
+    //还要先去避难区的人：开启了水平疏散、没有被放行继续疏散，也还没离开建筑
+    pub fn seeks_refuge(&self, para: &NeededParameters) -> bool {
+        para.horizontal_evacuation != HorizontalMode::Off && !self.released && !self.is_evacuated
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use crate::components::painting::*;
+use crate::components::TileType;
+
+use super::fire::BuildingFire;
+use super::smoke::BuildingSmoke;
+use super::NeededParameters;
+
+//一扇门：关着时挡烟挡火，推开需要时间；开着时按通行能力放人通过
+#[derive(Debug, Clone, PartialEq)]
+pub struct Door {
+    pub mode: DoorMode,
+    pub fire_door: bool,
+    pub flow: f32,
+    pub open: bool,
+    pub opening: Option<f32>, //正在被推开，剩余时间
+    pub closing: f32,         //自闭门自动关上前的剩余时间
+    pub budget: f32,          //积累的通行能力，满1人放行一人
+    pub passed: usize,
+    pub fire_exposure: f32, //关着时被火势波及的累计时间
+    pub failed: bool,       //被火烧穿，不再挡烟挡火
+}
+
+impl Door {
+    pub fn new(mode: DoorMode, fire_door: bool, flow: f32) -> Self {
+        Door {
+            mode,
+            fire_door,
+            flow,
+            open: mode == DoorMode::Open,
+            opening: None,
+            closing: 0.0,
+            budget: flow.max(1.0),
+            passed: 0,
+            fire_exposure: 0.0,
+            failed: false,
+        }
+    }
+
+    //推进dt秒：积累通行能力，推门和自闭门计时，返回门是否开着
+    pub fn tick(&mut self, dt: f32) -> bool {
+        self.budget = (self.budget + self.flow * dt).min(self.flow.max(1.0));
+        if let Some(time) = self.opening {
+            if time > dt {
+                self.opening = Some(time - dt);
+            } else {
+                self.opening = None;
+                self.open = true;
+            }
+        } else if self.open && self.mode == DoorMode::SelfClosing {
+            self.closing -= dt;
+            if self.closing <= 0.0 {
+                self.open = false;
+            }
+        }
+        self.open
+    }
+
+    //有人要通过这扇门，能通过时返回true；门关着时开始推门
+    pub fn try_pass(&mut self, para: &NeededParameters) -> bool {
+        if self.mode == DoorMode::Locked {
+            return false;
+        }
+        if !self.open {
+            if self.opening.is_none() {
+                self.opening = Some(para.door_open_time);
+            }
+            return false;
+        }
+        if self.budget < 1.0 {
+            return false;
+        }
+        self.budget -= 1.0;
+        self.passed += 1;
+        self.closing = para.door_close_time;
+        true
+    }
+
+    pub fn blocks_fire(&self) -> bool {
+        !self.open && !self.failed
+    }
+
+    //警报响起后防火门变为自闭门并立即关闭
+    pub fn release(&mut self) {
+        if self.fire_door && self.mode != DoorMode::Locked {
+            self.mode = DoorMode::SelfClosing;
+            self.closing = 0.0;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StoreyDoors {
+    pub doors: HashMap<(usize, usize), Door>,
+}
+
+impl StoreyDoors {
+    //地图上的每个门格都是一扇门，地图集里有设置的按设置，否则为常开的普通门
+    pub fn init_from_map(&mut self, map: &Map, specs: &[&DoorSpec], para: &NeededParameters) {
+        for i in 0..map.tiles.len() {
+            for j in 0..map.tiles[0].len() {
+                if map.tiles[i][j] != TileType::Door {
+                    continue;
+                }
+                let door = match specs.iter().find(|spec| spec.tile == (i, j)) {
+                    Some(spec) => Door::new(
+                        spec.mode,
+                        spec.fire_door,
+                        spec.flow.unwrap_or(para.door_flow),
+                    ),
+                    None => Door::new(DoorMode::Open, false, para.door_flow),
+                };
+                self.doors.insert((i, j), door);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BuildingDoors {
+    pub maps: HashMap<MapMarker, StoreyDoors>,
+}
+
+impl BuildingDoors {
+    //初始化每一层的门，关着的门格挡火，格上的烟不扩散
+    pub fn init_from_album(
+        &mut self,
+        map_album: &MapAlbum,
+        para: &NeededParameters,
+        fire: &mut BuildingFire,
+        smoke: &mut BuildingSmoke,
+    ) {
+        for (marker, map) in map_album.maps.iter() {
+            let specs: Vec<&DoorSpec> = map_album
+                .doors
+                .iter()
+                .filter(|spec| &spec.storey == marker)
+                .collect();
+            let mut storey_doors = StoreyDoors::default();
+            storey_doors.init_from_map(map, &specs, para);
+            self.maps.insert(marker.clone(), storey_doors);
+        }
+        self.update_barriers(fire, smoke);
+    }
+
+    //推进所有的门，这一步里关上、打开或烧穿的门马上同步到火的挡火格和烟雾
+    pub fn tick(&mut self, dt: f32, fire: &mut BuildingFire, smoke: &mut BuildingSmoke) {
+        for storey_doors in self.maps.values_mut() {
+            for door in storey_doors.doors.values_mut() {
+                door.tick(dt);
+            }
+        }
+        self.update_barriers(fire, smoke);
+    }
+
+    fn update_barriers(&self, fire: &mut BuildingFire, smoke: &mut BuildingSmoke) {
+        for (marker, storey_doors) in self.maps.iter() {
+            let storey_fire = fire.maps.get_mut(marker).unwrap();
+            let storey_smoke = smoke.maps.get_mut(marker).unwrap();
+            for (&(i, j), door) in storey_doors.doors.iter() {
+                storey_fire.barrier[i][j] = door.blocks_fire();
+                storey_smoke.map[i][j].set_closed(door.blocks_fire());
+            }
+        }
+    }
+
+    //关着的门被火势波及时开始计时，超过耐火时间后被烧穿
+    pub fn expose_to_fire(&mut self, dt: f32, fire: &BuildingFire, para: &NeededParameters) {
+        for (marker, storey_doors) in self.maps.iter_mut() {
+            let storey_fire = &fire.maps[marker];
+            for (&(i, j), door) in storey_doors.doors.iter_mut() {
+                if door.blocks_fire() && storey_fire.heated.contains(&(i, j)) {
+                    door.fire_exposure += dt;
+                    door.failed = door.fire_exposure >= para.door_fire_resistance;
+                }
+            }
+        }
+    }
+
+    //烧穿的门数
+    pub fn failed_num(&self) -> usize {
+        self.maps
+            .values()
+            .flat_map(|x| x.doors.values())
+            .filter(|x| x.failed)
+            .count()
+    }
+
+    //不是门的格子总能通过
+    pub fn try_pass(
+        &mut self,
+        storey: &MapMarker,
+        tile: (usize, usize),
+        para: &NeededParameters,
+    ) -> bool {
+        match self.maps.get_mut(storey).unwrap().doors.get_mut(&tile) {
+            Some(door) => door.try_pass(para),
+            None => true,
+        }
+    }
+
+    //警报响起，这一层的防火门关闭
+    pub fn release_fire_doors(&mut self, storey: &MapMarker) {
+        for door in self.maps.get_mut(storey).unwrap().doors.values_mut() {
+            door.release();
+        }
+    }
+}
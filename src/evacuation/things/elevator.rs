@@ -0,0 +1,248 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::VecDeque;
+
+use crate::components::painting::*;
+
+use super::fire::{BuildingFire, Fire};
+use super::human::Human;
+use super::smoke::BuildingSmoke;
+use super::NeededParameters;
+
+//轿厢状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum CarState {
+    Idle,
+    Moving(f32),    //剩余运行时间
+    DoorsOpen(f32), //剩余开门时间
+}
+
+//一部电梯：按呼叫的先后顺序去各层接人，载人后直接开往离首层最近的停靠层
+//火或烟到达任一停靠格后进入消防锁定，不再接人，轿厢内的人送到后停在该层
+#[derive(Debug, Clone, PartialEq)]
+pub struct Elevator {
+    pub name: String,
+    pub stops: Vec<StairEnd>,
+    pub elevations: Vec<f32>,
+    pub ground: usize, //离首层最近的停靠层在stops中的序号
+    pub floor: usize,  //轿厢所在（或刚离开）的停靠层
+    pub target: usize,
+    pub state: CarState,
+    pub passengers: Vec<usize>,
+    pub calls: VecDeque<usize>,
+    pub locked_at: Option<f32>,
+    pub trips: usize,
+    pub served: usize,
+}
+
+//一部电梯在一次模拟中的使用情况
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElevatorUsage {
+    pub name: String,
+    pub trips: usize,
+    pub passengers: usize,
+    pub locked_at: Option<f32>,
+}
+
+impl Elevator {
+    //轿厢初始停在离首层最近的停靠层
+    pub fn from_shaft(shaft: &ElevatorShaft, map_album: &MapAlbum) -> Self {
+        let ground = map_album.elevator_ground(shaft).unwrap_or(0);
+        Elevator {
+            name: shaft.name.clone(),
+            stops: shaft.stops.clone(),
+            elevations: shaft
+                .stops
+                .iter()
+                .map(|stop| map_album.storey_info(&stop.storey).elevation)
+                .collect(),
+            ground,
+            floor: ground,
+            target: ground,
+            state: CarState::Idle,
+            passengers: Vec::new(),
+            calls: VecDeque::new(),
+            locked_at: None,
+            trips: 0,
+            served: 0,
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked_at.is_some()
+    }
+
+    //电梯在某层的停靠格在stops中的序号
+    pub fn stop_of(&self, storey: &MapMarker) -> Option<usize> {
+        self.stops.iter().position(|stop| &stop.storey == storey)
+    }
+
+    //有人在停靠格等候时呼叫电梯，同一层只排一次队
+    pub fn call(&mut self, stop: usize) {
+        if !self.calls.contains(&stop) {
+            self.calls.push_back(stop);
+        }
+    }
+
+    //火烧到停靠格或其四邻，或停靠格烟雾浓度达到上限
+    pub fn is_exposed(&self, fire: &BuildingFire, smoke: &BuildingSmoke, smoke_limit: f32) -> bool {
+        self.stops.iter().any(|stop| {
+            let (i, j) = stop.tile;
+            let storey_fire = &fire.maps[&stop.storey].map;
+            let burning = [(0, 0), (0, 1), (0, -1), (1, 0), (-1, 0)]
+                .iter()
+                .any(|&(di, dj)| {
+                    let (ni, nj) = (i as i32 + di, j as i32 + dj);
+                    ni >= 0
+                        && nj >= 0
+                        && (ni as usize) < storey_fire.len()
+                        && (nj as usize) < storey_fire[0].len()
+                        && matches!(storey_fire[ni as usize][nj as usize], Fire::On(_))
+                });
+            burning || smoke.maps[&stop.storey].map[i][j].get_density() >= smoke_limit
+        })
+    }
+
+    fn depart(&mut self, to: usize, para: &NeededParameters) {
+        self.target = to;
+        if to == self.floor {
+            self.state = CarState::DoorsOpen(para.elevator_door_time);
+        } else {
+            let distance = (self.elevations[to] - self.elevations[self.floor]).abs();
+            self.state = CarState::Moving(distance / para.elevator_speed);
+        }
+    }
+
+    //在当前停靠层等候的人
+    fn waiting(&self, id: usize, humans: &[Human]) -> Vec<usize> {
+        let stop = &self.stops[self.floor];
+        humans
+            .iter()
+            .filter(|human| {
+                human.elevator == Some(id)
+                    && !human.riding
+                    && !human.is_dead
+                    && human.storey == stop.storey
+                    && human.now_tile == stop.tile
+            })
+            .map(|human| human.id)
+            .collect()
+    }
+
+    //推进dt秒，id为电梯的序号，返回在目的层走出轿厢的人
+    pub fn run(
+        &mut self,
+        id: usize,
+        dt: f32,
+        para: &NeededParameters,
+        humans: &mut [Human],
+    ) -> Vec<usize> {
+        let mut arrived = Vec::new();
+        match self.state {
+            CarState::Idle => {
+                if !self.passengers.is_empty() || (self.is_locked() && self.floor != self.ground) {
+                    self.depart(self.ground, para);
+                } else if !self.is_locked() {
+                    if let Some(stop) = self.calls.pop_front() {
+                        self.depart(stop, para);
+                    }
+                }
+            }
+            CarState::Moving(time) => {
+                if time > dt {
+                    self.state = CarState::Moving(time - dt);
+                } else {
+                    self.floor = self.target;
+                    self.trips += 1;
+                    self.state = CarState::DoorsOpen(para.elevator_door_time);
+                }
+            }
+            CarState::DoorsOpen(time) => {
+                if self.floor == self.ground {
+                    self.served += self.passengers.len();
+                    arrived.append(&mut self.passengers);
+                } else if !self.is_locked() {
+                    let room = para.elevator_capacity.saturating_sub(self.passengers.len());
+                    for human_id in self.waiting(id, humans).into_iter().take(room) {
+                        humans[human_id].riding = true;
+                        self.passengers.push(human_id);
+                    }
+                }
+                if time > dt {
+                    self.state = CarState::DoorsOpen(time - dt);
+                } else {
+                    self.state = CarState::Idle;
+                    //没挤上去的人再呼叫一次
+                    if !self.is_locked() && !self.waiting(id, humans).is_empty() {
+                        self.call(self.floor);
+                    }
+                }
+            }
+        }
+        arrived
+    }
+
+    pub fn usage(&self) -> ElevatorUsage {
+        ElevatorUsage {
+            name: self.name.clone(),
+            trips: self.trips,
+            passengers: self.served,
+            locked_at: self.locked_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BuildingElevators {
+    pub elevators: Vec<Elevator>,
+}
+
+impl BuildingElevators {
+    //序号与map_album.elevators一致
+    pub fn init_from_album(&mut self, map_album: &MapAlbum) {
+        self.elevators = map_album
+            .elevators
+            .iter()
+            .map(|shaft| Elevator::from_shaft(shaft, map_album))
+            .collect();
+    }
+
+    //检查火和烟，返回这一步新进入消防锁定的电梯
+    pub fn lockout(
+        &mut self,
+        fire: &BuildingFire,
+        smoke: &BuildingSmoke,
+        smoke_limit: f32,
+        time: f32,
+    ) -> Vec<usize> {
+        let mut locked = Vec::new();
+        for (id, elevator) in self.elevators.iter_mut().enumerate() {
+            if !elevator.is_locked() && elevator.is_exposed(fire, smoke, smoke_limit) {
+                elevator.locked_at = Some(time);
+                elevator.calls.clear();
+                locked.push(id);
+            }
+        }
+        locked
+    }
+
+    //推进所有电梯，返回走出轿厢的人及其所在的停靠格
+    pub fn run(
+        &mut self,
+        dt: f32,
+        para: &NeededParameters,
+        humans: &mut [Human],
+    ) -> Vec<(usize, StairEnd)> {
+        let mut arrived = Vec::new();
+        for (id, elevator) in self.elevators.iter_mut().enumerate() {
+            for human_id in elevator.run(id, dt, para, humans) {
+                arrived.push((human_id, elevator.stops[elevator.floor].clone()));
+            }
+        }
+        arrived
+    }
+
+    pub fn usage(&self) -> Vec<ElevatorUsage> {
+        self.elevators.iter().map(|x| x.usage()).collect()
+    }
+}
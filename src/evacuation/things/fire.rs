@@ -1,8 +1,15 @@
 use crate::components::{painting::*, TileType};
 use bevy::prelude::*;
 
+use rand::rngs::StdRng;
 use rand::Rng;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::time::Duration;
+
+use super::smoke::BuildingSmoke;
+use super::NeededParameters;
+
+type Tiles = Vec<(usize, usize)>;
 
 #[derive(Component, Debug, Clone, PartialEq)]
 pub struct StoreyFire {
@@ -10,6 +17,12 @@ pub struct StoreyFire {
     pub burning_time: f32,
     pub smoking_time: f32,
     pub k: f32,
+    pub wet: Vec<Vec<bool>>, //在喷水范围内的格，不会被引燃，燃烧时产烟减少
+    pub wet_smoke: f32,      //喷水范围内燃烧格的产烟比例
+    pub ignition_delay: f32, //被火势波及的可燃物平均多久被引燃（秒），不大于0时立即引燃
+    pub burning: BTreeSet<(usize, usize)>, //正在燃烧的格，引燃和燃烧都只看这些格
+    pub barrier: Vec<Vec<bool>>, //挡火的格：墙和关着的门
+    pub heated: BTreeSet<(usize, usize)>, //这一步被火势波及的挡火格
 }
 
 impl StoreyFire {
@@ -19,6 +32,12 @@ impl StoreyFire {
             burning_time: 100.0,
             smoking_time: 1.0,
             k: 1.0,
+            wet: vec![vec![false; cols]; rows],
+            wet_smoke: 1.0,
+            ignition_delay: 0.0,
+            burning: BTreeSet::new(),
+            barrier: vec![vec![false; cols]; rows],
+            heated: BTreeSet::new(),
         }
     }
 
@@ -34,6 +53,12 @@ impl StoreyFire {
             burning_time,
             smoking_time,
             k,
+            wet: vec![vec![false; cols]; rows],
+            wet_smoke: 1.0,
+            ignition_delay: 0.0,
+            burning: BTreeSet::new(),
+            barrier: vec![vec![false; cols]; rows],
+            heated: BTreeSet::new(),
         }
     }
 
@@ -43,57 +68,192 @@ impl StoreyFire {
                 if map.tiles[i][j] == TileType::Furniture {
                     self.map[i][j] = Fire::Off;
                 }
+                if map.tiles[i][j] == TileType::Wall || map.tiles[i][j] == TileType::Black {
+                    self.barrier[i][j] = true;
+                }
             }
         }
     }
 
     //随机点燃
-    pub fn get_p_fire(&mut self, p: f64) {
-        let mut rng = rand::thread_rng();
+    pub fn get_p_fire(&mut self, p: f64, rng: &mut StdRng) {
         for i in 0..self.map.len() {
             for j in 0..self.map[0].len() {
                 if self.map[i][j] == Fire::Off {
                     if rng.gen_bool(p) {
-                        self.map[i][j] =
-                            Fire::On(FireTimer::new(self.burning_time, self.smoking_time));
+                        self.ignite((i, j), rng);
                     }
                 }
             }
         }
     }
 
-    //未燃烧的点按一定条件被引燃
-    pub fn get_neighbours_fire_on(&mut self) {
-        let mut fire_on_queue = VecDeque::new();
-        let mut fire_off_queue = VecDeque::new();
+    //点燃一格并记入正在燃烧的格
+    pub fn ignite(&mut self, tile: (usize, usize), rng: &mut StdRng) {
+        let (i, j) = tile;
+        self.map[i][j].fire_on(self.burning_time, self.smoking_time, rng);
+        if let Fire::On(_) = self.map[i][j] {
+            self.burning.insert(tile);
+        }
+    }
+
+    //熄灭一格（燃尽或被扑灭）
+    pub fn extinguish(&mut self, tile: (usize, usize)) {
+        self.map[tile.0][tile.1].fire_off();
+        self.burning.remove(&tile);
+    }
+
+    //影响半径：燃烧时间最长的格在燃尽前能引燃的最远距离（格）
+    pub fn reach(&self) -> usize {
+        if self.k > 0.0 {
+            (1.2 * self.burning_time / self.k).ceil() as usize
+        } else {
+            self.map.len().max(self.map[0].len())
+        }
+    }
+
+    //从source出发、不穿过挡火格能走到的in_range内的格，以及范围内挡住去路的挡火格
+    //seen记录每一格最后一次被哪个出发点（stamp）走到过，可以在多个出发点之间复用
+    pub fn flood(
+        &self,
+        source: (usize, usize),
+        in_range: impl Fn((usize, usize)) -> bool,
+        seen: &mut [Vec<usize>],
+        stamp: usize,
+    ) -> (Tiles, Tiles) {
         let rows = self.map.len();
         let cols = self.map[0].len();
-        for i in 0..rows {
-            for j in 0..cols {
-                if let Fire::On(_) = &self.map[i][j] {
-                    fire_on_queue.push_back((i, j));
+        let (x, y) = source;
+        let mut reached = Vec::new();
+        let mut blocked = Vec::new();
+        seen[x][y] = stamp;
+        let mut queue = VecDeque::from([source]);
+        while let Some((i, j)) = queue.pop_front() {
+            reached.push((i, j));
+            for (di, dj) in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+                let (ni, nj) = (i as i32 + di, j as i32 + dj);
+                if ni < 0 || nj < 0 || ni >= rows as i32 || nj >= cols as i32 {
+                    continue;
+                }
+                let next = (ni as usize, nj as usize);
+                if seen[next.0][next.1] == stamp || !in_range(next) {
+                    continue;
                 }
-                if let Fire::Off = &self.map[i][j] {
-                    fire_off_queue.push_back((i, j));
+                seen[next.0][next.1] = stamp;
+                if self.barrier[next.0][next.1] {
+                    blocked.push(next);
+                } else {
+                    queue.push_back(next);
                 }
             }
         }
+        (reached, blocked)
+    }
 
-        while let Some((i, j)) = fire_off_queue.pop_front() {
-            for (x, y) in fire_on_queue.iter() {
-                let fire_time = self.map[*x][*y].get_burning_time();
-                let distance2 =
-                    ((i as i32 - *x as i32).pow(2) + (j as i32 - *y as i32).pow(2)) as f32;
-                let distance = distance2.sqrt();
-                if fire_time / distance > self.k {
-                    self.map[i][j].fire_on(self.burning_time, self.smoking_time);
+    //未燃烧的点按一定条件被引燃：燃烧了t秒的格能波及距离d满足t/d>k、且不隔着墙和关着的门的格
+    //被n个燃烧格波及的格在dt秒内着火的概率为1-exp(-n*dt/ignition_delay)
+    //用挡火格的二维前缀和判断每个燃烧格的影响范围内有没有挡火格：没有时范围就是一个圆，
+    //按行把圆记进差分数组，只花O(半径)；有挡火格时才从燃烧格出发在范围内走一遍
+    pub fn spread(&mut self, dt: f32, rng: &mut StdRng) {
+        let rows = self.map.len();
+        let cols = self.map[0].len();
+        let reach = self.reach();
+        let barriers = SummedArea::new(&self.barrier);
+        let mut exposed = vec![vec![0usize; cols]; rows];
+        let mut spans = vec![vec![0i64; cols + 1]; rows];
+        let mut seen = vec![vec![0; cols]; rows];
+        self.heated.clear();
+        for (n, &source) in self.burning.iter().enumerate() {
+            let (x, y) = source;
+            let fire_time = self.map[x][y].get_burning_time();
+            //燃烧了t秒的格能波及行、列方向上分别相距di、dj且t/d>k的格
+            let within = |di: usize, dj: usize| {
+                let distance2 = (di.pow(2) + dj.pow(2)) as f32;
+                di <= reach && dj <= reach && fire_time / distance2.sqrt() > self.k
+            };
+            //圆内每一行的半宽，行距越大半宽越小
+            let mut half = Vec::new();
+            let mut dj = reach;
+            for di in 0..=reach {
+                if !within(di, 0) {
                     break;
                 }
+                while !within(di, dj) {
+                    dj -= 1;
+                }
+                half.push(dj);
+            }
+            let r = half.len().saturating_sub(1);
+            let corner0 = (x.saturating_sub(r), y.saturating_sub(r));
+            let corner1 = ((x + r).min(rows - 1), (y + r).min(cols - 1));
+            if barriers.count(corner0, corner1) == 0 {
+                for (di, &dj) in half.iter().enumerate() {
+                    let (j0, j1) = (y.saturating_sub(dj), (y + dj).min(cols - 1));
+                    let above = x.checked_sub(di);
+                    let below = Some(x + di).filter(|&i| di > 0 && i < rows);
+                    for i in [above, below].into_iter().flatten() {
+                        spans[i][j0] += 1;
+                        spans[i][j1 + 1] -= 1;
+                    }
+                }
+            } else {
+                let in_range = |(i, j): (usize, usize)| within(i.abs_diff(x), j.abs_diff(y));
+                let (reached, blocked) = self.flood(source, in_range, &mut seen, n + 1);
+                for (i, j) in reached {
+                    exposed[i][j] += 1;
+                }
+                self.heated.extend(blocked);
+            }
+        }
+
+        for i in 0..rows {
+            let mut span = 0;
+            for j in 0..cols {
+                span += spans[i][j];
+                let n = exposed[i][j] + span as usize;
+                if n == 0 || self.map[i][j] != Fire::Off || self.wet[i][j] {
+                    continue;
+                }
+                let p = if self.ignition_delay > 0.0 {
+                    1.0 - (-(n as f32) * dt / self.ignition_delay).exp()
+                } else {
+                    1.0
+                };
+                if p >= 1.0 || rng.gen_bool(p as f64) {
+                    self.ignite((i, j), rng);
+                }
             }
         }
     }
 }
 
+//二维前缀和，O(1)数出矩形内为true的格数
+struct SummedArea {
+    sums: Vec<Vec<usize>>,
+}
+
+impl SummedArea {
+    fn new(grid: &[Vec<bool>]) -> Self {
+        let rows = grid.len();
+        let cols = grid[0].len();
+        let mut sums = vec![vec![0; cols + 1]; rows + 1];
+        for i in 0..rows {
+            for j in 0..cols {
+                sums[i + 1][j + 1] =
+                    sums[i][j + 1] + sums[i + 1][j] - sums[i][j] + grid[i][j] as usize;
+            }
+        }
+        SummedArea { sums }
+    }
+
+    //左上角corner0到右下角corner1（闭区间）内的格数
+    fn count(&self, corner0: (usize, usize), corner1: (usize, usize)) -> usize {
+        let (i0, j0) = corner0;
+        let (i1, j1) = (corner1.0 + 1, corner1.1 + 1);
+        self.sums[i1][j1] + self.sums[i0][j0] - self.sums[i0][j1] - self.sums[i1][j0]
+    }
+}
+
 #[derive(Component, Default, Debug, Clone, PartialEq)]
 pub enum Fire {
     #[default]
@@ -114,10 +274,10 @@ impl FireTile {
 }
 
 impl Fire {
-    pub fn fire_on(&mut self, burning_time: f32, smoking_time: f32) {
+    pub fn fire_on(&mut self, burning_time: f32, smoking_time: f32, rng: &mut StdRng) {
         match self {
             Fire::Off => {
-                *self = Fire::On(FireTimer::new(burning_time, smoking_time));
+                *self = Fire::On(FireTimer::new(burning_time, smoking_time, rng));
             }
             _ => {}
         }
@@ -156,8 +316,7 @@ impl Default for FireTimer {
 }
 
 impl FireTimer {
-    pub fn new(burning_time: f32, smoking_time: f32) -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn new(burning_time: f32, smoking_time: f32, rng: &mut StdRng) -> Self {
         let burning_time = rng.gen_range(0.8 * burning_time..1.2 * burning_time);
         let smoking_time = rng.gen_range(0.8 * smoking_time..1.2 * smoking_time);
         FireTimer {
@@ -185,3 +344,65 @@ impl Default for BuildingFire {
         }
     }
 }
+
+impl BuildingFire {
+    //根据地图和参数初始化每一层的火
+    pub fn init_from_album(&mut self, map_album: &MapAlbum, para: &NeededParameters) {
+        for (marker, map) in map_album.maps.iter() {
+            let mut storey_fire = StoreyFire::new_with(
+                map.tiles.len(),
+                map.tiles[0].len(),
+                para.burning_time,
+                para.smoking_time,
+                para.k,
+            );
+            storey_fire.init_from_map(map);
+            storey_fire.wet_smoke = 1.0 - para.sprinkler_smoke_cut;
+            storey_fire.ignition_delay = para.ignition_delay;
+            self.maps.insert(marker.clone(), storey_fire);
+        }
+    }
+
+    //初始随机点燃
+    pub fn get_p_fire(&mut self, p: f64, rng: &mut StdRng) {
+        for marker in sorted_markers(&self.maps) {
+            self.maps.get_mut(&marker).unwrap().get_p_fire(p, rng);
+        }
+    }
+
+    //引燃，dt为这一步经过的时间（秒）
+    pub fn ignition(&mut self, dt: f32, rng: &mut StdRng) {
+        for marker in sorted_markers(&self.maps) {
+            self.maps.get_mut(&marker).unwrap().spread(dt, rng);
+        }
+    }
+
+    //燃烧：计时器前进，产生烟，燃尽熄灭
+    pub fn burning(&mut self, building_smoke: &mut BuildingSmoke, s: f32, delta: Duration) {
+        for (marker, storey_fire) in self.maps.iter_mut() {
+            let storey_smoke = building_smoke.maps.get_mut(marker).unwrap();
+            let burning: Vec<(usize, usize)> = storey_fire.burning.iter().copied().collect();
+            for (i, j) in burning {
+                if let Fire::On(timer) = &mut storey_fire.map[i][j] {
+                    timer.burning_timer.tick(delta);
+                    timer.smoking_timer.tick(delta);
+                    if timer.smoking_timer.finished() {
+                        let wet = if storey_fire.wet[i][j] {
+                            storey_fire.wet_smoke
+                        } else {
+                            1.0
+                        };
+                        storey_smoke.add(
+                            (i, j),
+                            wet * s * timer.burning_timer.elapsed_secs()
+                                / timer.burning_timer.duration().as_secs_f32(),
+                        );
+                    }
+                    if timer.burning_timer.finished() {
+                        storey_fire.extinguish((i, j));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::components::painting::*;
+
+use super::fire::{BuildingFire, StoreyFire};
+use super::human::Human;
+use super::NeededParameters;
+
+//每一层的热环境：燃烧格附近的气温和火焰的辐射热通量，热量不穿过墙和关着的门
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoreyHeat {
+    pub temperature: Vec<Vec<f32>>, //气温（°C）
+    pub radiation: Vec<Vec<f32>>,   //辐射热通量（kW/m²）
+}
+
+impl StoreyHeat {
+    pub fn new(rows: usize, cols: usize, ambient: f32) -> Self {
+        StoreyHeat {
+            temperature: vec![vec![ambient; cols]; rows],
+            radiation: vec![vec![0.0; cols]; rows],
+        }
+    }
+
+    //从每个燃烧格出发，在heat_radius格内不穿过挡火格地向外传热：
+    //距离d处的目标温升为火焰温升/(1+d²)，辐射热通量为flame_radiant_flux/max(d,1)²，多个燃烧格叠加
+    //气温以heat_time_constant为时间常数趋向目标温度，辐射随火焰即时变化
+    pub fn update(&mut self, fire: &StoreyFire, para: &NeededParameters, dt: f32) {
+        let rows = self.temperature.len();
+        let cols = self.temperature[0].len();
+        let r = para.heat_radius;
+        let flame_rise = (para.flame_temperature - para.ambient_temperature).max(0.0);
+        let mut rise = vec![vec![0.0; cols]; rows];
+        for row in self.radiation.iter_mut() {
+            row.fill(0.0);
+        }
+        let mut seen = vec![vec![0; cols]; rows];
+        for (n, &(x, y)) in fire.burning.iter().enumerate() {
+            let stamp = n + 1;
+            seen[x][y] = stamp;
+            let mut queue = VecDeque::from([(x, y)]);
+            while let Some((i, j)) = queue.pop_front() {
+                let distance2 = (i.abs_diff(x).pow(2) + j.abs_diff(y).pow(2)) as f32;
+                rise[i][j] += flame_rise / (1.0 + distance2);
+                self.radiation[i][j] += para.flame_radiant_flux / distance2.max(1.0);
+                for (di, dj) in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+                    let (ni, nj) = (i as i32 + di, j as i32 + dj);
+                    if ni < 0 || nj < 0 || ni >= rows as i32 || nj >= cols as i32 {
+                        continue;
+                    }
+                    let (ni, nj) = (ni as usize, nj as usize);
+                    if seen[ni][nj] == stamp
+                        || ni.abs_diff(x) > r
+                        || nj.abs_diff(y) > r
+                        || fire.barrier[ni][nj]
+                    {
+                        continue;
+                    }
+                    seen[ni][nj] = stamp;
+                    queue.push_back((ni, nj));
+                }
+            }
+        }
+        let relax = match para.heat_time_constant > 0.0 {
+            true => 1.0 - (-dt / para.heat_time_constant).exp(),
+            false => 1.0,
+        };
+        for (row, rise_row) in self.temperature.iter_mut().zip(rise.iter()) {
+            for (temperature, rise) in row.iter_mut().zip(rise_row.iter()) {
+                let target = para.ambient_temperature + rise.min(flame_rise);
+                *temperature += (target - *temperature) * relax;
+            }
+        }
+    }
+
+    //比环境温度高出的度数
+    pub fn rise(&self, tile: (usize, usize), para: &NeededParameters) -> f32 {
+        (self.temperature[tile.0][tile.1] - para.ambient_temperature).max(0.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BuildingHeat {
+    pub maps: HashMap<MapMarker, StoreyHeat>,
+    pub peak: f32, //模拟过程中出现过的最高气温（°C）
+}
+
+impl BuildingHeat {
+    pub fn init_from_album(&mut self, map_album: &MapAlbum, para: &NeededParameters) {
+        for (marker, map) in map_album.maps.iter() {
+            let storey_heat = StoreyHeat::new(
+                map.tiles.len(),
+                map.tiles[0].len(),
+                para.ambient_temperature,
+            );
+            self.maps.insert(marker.clone(), storey_heat);
+        }
+        self.peak = para.ambient_temperature;
+    }
+
+    pub fn update(&mut self, fire: &BuildingFire, para: &NeededParameters, dt: f32) {
+        for (marker, storey_heat) in self.maps.iter_mut() {
+            storey_heat.update(&fire.maps[marker], para, dt);
+            for row in storey_heat.temperature.iter() {
+                self.peak = row.iter().fold(self.peak, |a, &b| a.max(b));
+            }
+        }
+    }
+}
+
+//ISO 13571的热剂量率（每秒的FED）：
+//对流 t=5e7·T^-3.4 分钟，辐射 t=6.9/q^1.33 分钟（q>1.7kW/m²），环境温度下不计
+pub fn heat_dose_rate(temperature: f32, radiation: f32, para: &NeededParameters) -> f32 {
+    let mut rate = 0.0;
+    if temperature > para.ambient_temperature + 1.0 {
+        rate += 1.0 / (5e7 * temperature.powf(-3.4) * 60.0);
+    }
+    if radiation > 1.7 {
+        rate += 1.0 / (6.9 / radiation.powf(1.33) * 60.0);
+    }
+    rate
+}
+
+impl Human {
+    //累计热剂量，剂量每增加1损失全部最大血量
+    pub fn heat_damage(&mut self, heat: &StoreyHeat, para: &NeededParameters, dt: f32) {
+        let (i, j) = self.now_tile;
+        let dose = heat_dose_rate(heat.temperature[i][j], heat.radiation[i][j], para) * dt;
+        self.heat_dose += dose;
+        self.hp -= dose * self.max_hp;
+        if self.hp <= 0.0 {
+            self.is_dead = true;
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 
+use rand::rngs::StdRng;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
@@ -14,12 +16,20 @@ use crate::components::*;
 
 use crate::systems::paper::{get_x, get_y};
 
+use super::behaviour::Behaviour;
+use super::heat::{BuildingHeat, StoreyHeat};
+use super::rescue::Role;
 use super::smoke::*;
+use super::NeededParameters;
 
 #[derive(Component, Debug, Clone, PartialEq)]
 pub struct StoreyPass {
     pub map: Vec<Vec<Pass>>,
     pub exit: Vec<(usize, usize)>,
+    pub stair: Vec<(usize, usize)>, //通向首层方向的楼梯格，首层上为空
+    pub elevator: Vec<(usize, usize)>, //通向首层方向且未锁定的电梯停靠格
+    pub main_exit: Vec<(usize, usize)>, //主出口格
+    pub refuge: Vec<(usize, usize)>, //安全分区里的避难格，不水平疏散时为空
 }
 
 #[derive(Component, Debug, Clone, PartialEq)]
@@ -31,11 +41,15 @@ pub enum Pass {
 #[derive(Component, Debug, Clone, PartialEq)]
 pub struct Danger {
     pub value: f32,
+    pub heat: f32, //高温折算成的额外距离（格）
 }
 
 impl Default for Danger {
     fn default() -> Self {
-        Danger { value: 0.0 }
+        Danger {
+            value: 0.0,
+            heat: 0.0,
+        }
     }
 }
 
@@ -49,6 +63,10 @@ impl StoreyPass {
         StoreyPass {
             map: vec![vec![Pass::Passable(Danger::default()); cols]; rows],
             exit: Vec::new(),
+            stair: Vec::new(),
+            elevator: Vec::new(),
+            main_exit: Vec::new(),
+            refuge: Vec::new(),
         }
     }
 
@@ -59,6 +77,7 @@ impl StoreyPass {
                 if map.tiles[i][j] == TileType::Wall
                     || map.tiles[i][j] == TileType::Furniture
                     || map.tiles[i][j] == TileType::Stone
+                    || map.tiles[i][j] == TileType::Black
                 {
                     self.map[i][j] = Pass::Impassable;
                 }
@@ -73,13 +92,38 @@ impl StoreyPass {
         self.exit = temp;
     }
 
+    //疏散目标：乘电梯的人去电梯停靠格，不在首层时去楼梯，在首层或没有这样的楼梯时去出口
+    pub fn targets(&self, use_elevator: bool) -> &Vec<(usize, usize)> {
+        if use_elevator && !self.elevator.is_empty() {
+            &self.elevator
+        } else if self.stair.is_empty() {
+            &self.exit
+        } else {
+            &self.stair
+        }
+    }
+
+    //按温升给每格加上额外的代价，规划路线时绕开高温（能燃烧的家具格本来就不能通行）
+    pub fn update_from_heat(&mut self, heat: &StoreyHeat, para: &NeededParameters) {
+        let rows = self.map.len();
+        let cols = self.map[0].len();
+        for i in 0..rows {
+            for j in 0..cols {
+                if let Pass::Passable(danger) = &mut self.map[i][j] {
+                    danger.heat = para.heat_route_weight * heat.rise((i, j), para) / 100.0;
+                }
+            }
+        }
+    }
+
     pub fn update_from_smoke(&mut self, smoke: &StoreySmoke) {
         let rows = self.map.len();
         let cols = self.map[0].len();
         for i in 0..rows {
             for j in 0..cols {
+                //关着的门按门里留着的烟计算
                 if let Pass::Passable(danger) = &mut self.map[i][j] {
-                    if let Smoke::Diffusible(density) = &smoke.map[i][j] {
+                    if let Smoke::Diffusible(density) | Smoke::Closed(density) = &smoke.map[i][j] {
                         danger.value = density.value;
                     }
                 }
@@ -101,6 +145,42 @@ impl Default for BuildingPass {
     }
 }
 
+impl BuildingPass {
+    //用当前的烟雾更新每一层的危险值
+    pub fn update_from_smoke(&mut self, building_smoke: &BuildingSmoke) {
+        for (marker, storey_pass) in self.maps.iter_mut() {
+            storey_pass.update_from_smoke(&building_smoke.maps[marker]);
+        }
+    }
+
+    //用当前的气温更新每一层的高温代价
+    pub fn update_from_heat(&mut self, building_heat: &BuildingHeat, para: &NeededParameters) {
+        for (marker, storey_pass) in self.maps.iter_mut() {
+            storey_pass.update_from_heat(&building_heat.maps[marker], para);
+        }
+    }
+
+    //根据地图初始化每一层的通行情况
+    pub fn init_from_album(&mut self, map_album: &MapAlbum) {
+        for (marker, map) in map_album.maps.iter() {
+            let mut storey_pass = StoreyPass::new(map.tiles.len(), map.tiles[0].len());
+            storey_pass.init_from_map(map);
+            storey_pass.main_exit = map_album.main_exits(marker);
+            //锁闭的门不可通行
+            for door in map_album.doors.iter() {
+                if &door.storey == marker && door.mode == DoorMode::Locked {
+                    storey_pass.map[door.tile.0][door.tile.1] = Pass::Impassable;
+                }
+            }
+            if !map_album.is_ground(marker) {
+                storey_pass.stair = map_album.stairs_toward_ground(marker);
+                storey_pass.elevator = map_album.elevators_toward_ground(marker);
+            }
+            self.maps.insert(marker.clone(), storey_pass);
+        }
+    }
+}
+
 #[derive(Component, Debug, Clone, PartialEq)]
 pub enum MoveDirection {
     Up,
@@ -121,6 +201,17 @@ impl MoveDirection {
         }
     }
 
+    //from_ij的逆运算
+    pub fn into_ij(&self) -> (i32, i32) {
+        match self {
+            MoveDirection::Up => (0, 1),
+            MoveDirection::Down => (0, -1),
+            MoveDirection::Left => (1, 0),
+            MoveDirection::Right => (-1, 0),
+            MoveDirection::Stay => (0, 0),
+        }
+    }
+
     pub fn from_ij(i: i32, j: i32) -> Self {
         match (i, j) {
             (i, j) if i == 0 && j == 1 => MoveDirection::Up,
@@ -159,6 +250,7 @@ impl PartialOrd for State {
 #[derive(Component, Debug, Clone, PartialEq)]
 pub struct StoreyEvacuation {
     pub map: Vec<Vec<Evacuate>>,
+    pub cost: Vec<Vec<usize>>, //到最近目标的加权距离，每格100
 }
 
 impl StoreyEvacuation {
@@ -173,22 +265,34 @@ impl StoreyEvacuation {
                 }
             }
         }
-        StoreyEvacuation { map }
+        StoreyEvacuation {
+            map,
+            cost: vec![vec![usize::MAX; cols]; rows],
+        }
     }
 
     //计算最佳逃生路线，根据pass（danger值取决于smoke浓度）更新，采用加权最短路径Dijkstra算法，每点储存应该通向的下一个点
-    pub fn update_from_pass(&mut self, pass: &StoreyPass, exit: (usize, usize)) {
+    //从所有目标同时出发，每走一格的代价为100*(1+smoke_weight*danger+高温折算的距离)
+    pub fn update_from_pass(
+        &mut self,
+        pass: &StoreyPass,
+        targets: &[(usize, usize)],
+        smoke_weight: f32,
+    ) {
+        *self = StoreyEvacuation::init_from_pass(pass);
         let map = &pass.map;
         let rows = map.len();
         let cols = map[0].len();
         let mut queue = BinaryHeap::new();
-        let mut cost = vec![vec![usize::MAX; cols]; rows];
+        let cost = &mut self.cost;
 
-        cost[exit.0][exit.1] = 0;
-        queue.push(State {
-            cost: 0,
-            position: exit,
-        });
+        for &target in targets {
+            cost[target.0][target.1] = 0;
+            queue.push(State {
+                cost: 0,
+                position: target,
+            });
+        }
 
         while let Some(State {
             cost: now_cost,
@@ -206,7 +310,9 @@ impl StoreyEvacuation {
                         let new_i = new_i as usize;
                         let new_j = new_j as usize;
                         if let Pass::Passable(danger) = &map[new_i][new_j] {
-                            let new_cost = now_cost + danger.value as usize;
+                            let step = 100.0
+                                * (1.0 + smoke_weight * danger.value.max(0.0) + danger.heat);
+                            let new_cost = now_cost + step as usize;
                             if new_cost < cost[new_i][new_j] {
                                 cost[new_i][new_j] = new_cost;
                                 queue.push(State {
@@ -224,14 +330,152 @@ impl StoreyEvacuation {
             }
         }
     }
+
+    //沿着疏散场从start走到目标的路径（不含start），走不到时返回None
+    pub fn path_from(&self, start: (usize, usize)) -> Option<VecDeque<(usize, usize)>> {
+        if self.cost[start.0][start.1] == usize::MAX {
+            return None;
+        }
+        let mut path = VecDeque::new();
+        let mut now = start;
+        while self.cost[now.0][now.1] > 0 {
+            let Evacuate::Move(direction) = &self.map[now.0][now.1] else {
+                return None;
+            };
+            let (di, dj) = direction.into_ij();
+            if (di, dj) == (0, 0) || path.len() > self.map.len() * self.map[0].len() {
+                return None;
+            }
+            now = ((now.0 as i32 + di) as usize, (now.1 as i32 + dj) as usize);
+            path.push_back(now);
+        }
+        Some(path)
+    }
+}
+
+//把相连的目标格分成组，每组是一个出口、一部楼梯或一部电梯的停靠格
+pub fn connected_groups(tiles: &[(usize, usize)]) -> Vec<Vec<(usize, usize)>> {
+    let all: HashSet<(usize, usize)> = tiles.iter().copied().collect();
+    let mut seen = HashSet::new();
+    let mut groups = Vec::new();
+    for &tile in tiles {
+        if !seen.insert(tile) {
+            continue;
+        }
+        let mut group = Vec::new();
+        let mut queue = VecDeque::from([tile]);
+        while let Some((i, j)) = queue.pop_front() {
+            group.push((i, j));
+            for (di, dj) in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+                let next = ((i as i32 + di) as usize, (j as i32 + dj) as usize);
+                if all.contains(&next) && seen.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        group.sort();
+        groups.push(group);
+    }
+    groups
+}
+
+//一组目标格以及通向它的疏散场
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetGroup {
+    pub tiles: Vec<(usize, usize)>,
+    pub main: bool,  //主出口
+    pub lift: bool,  //电梯停靠格
+    pub stair: bool, //楼梯
+    pub refuge: bool, //安全分区的避难区
+    pub field: StoreyEvacuation,
+}
+
+impl TargetGroup {
+    //组里最小的格，重建目标组后同一组的编号不变
+    pub fn key(&self) -> (usize, usize) {
+        self.tiles[0]
+    }
 }
 
 #[derive(Resource, Debug, Clone)]
 pub struct BuildingEvacuation {
-    pub maps: HashMap<MapMarker, StoreyEvacuation>,
+    pub maps: HashMap<MapMarker, Vec<TargetGroup>>,
+    pub queues: HashMap<MapMarker, HashMap<(usize, usize), usize>>, //按组的编号记排队人数
     pub now_map: MapMarker,
 }
 
+impl Default for BuildingEvacuation {
+    fn default() -> Self {
+        BuildingEvacuation {
+            maps: HashMap::new(),
+            queues: HashMap::new(),
+            now_map: MapMarker {
+                name: "default".to_string(),
+            },
+        }
+    }
+}
+
+impl BuildingEvacuation {
+    //根据每一层当前的通行情况重新计算每组目标的疏散场
+    pub fn update_from_pass(&mut self, building_pass: &BuildingPass, smoke_weight: f32) {
+        for (marker, pass) in building_pass.maps.iter() {
+            let mut groups = Vec::new();
+            for (targets, lift, refuge) in [
+                (pass.targets(false), false, false),
+                (&pass.elevator, true, false),
+                (&pass.refuge, false, true),
+            ] {
+                for tiles in connected_groups(targets) {
+                    let mut field = StoreyEvacuation::init_from_pass(pass);
+                    field.update_from_pass(pass, &tiles, smoke_weight);
+                    groups.push(TargetGroup {
+                        main: tiles.iter().any(|tile| pass.main_exit.contains(tile)),
+                        lift,
+                        stair: !lift && !refuge && !pass.stair.is_empty(),
+                        refuge,
+                        tiles,
+                        field,
+                    });
+                }
+            }
+            //目标组重建后位置会变，排队人数按组的编号保留，去掉已经没有的组
+            let queues = self.queues.entry(marker.clone()).or_default();
+            queues.retain(|key, _| groups.iter().any(|group| group.key() == *key));
+            self.maps.insert(marker.clone(), groups);
+        }
+    }
+
+    //统计每组目标前排队的人：离它radius格以内、正走向它的人，已在避难区里待着的人不算
+    pub fn update_queues(&mut self, humans: &[Human], radius: usize) {
+        for (marker, groups) in self.maps.iter() {
+            let queues = self.queues.get_mut(marker).unwrap();
+            queues.clear();
+            for human in humans.iter() {
+                if &human.storey != marker
+                    || human.is_dead
+                    || human.is_evacuated
+                    || human.riding
+                    || human.refuge_at.is_some()
+                {
+                    continue;
+                }
+                let (i, j) = human.now_tile;
+                for group in groups.iter() {
+                    let near = group.tiles.iter().any(|&(x, y)| {
+                        (x as i32 - i as i32).unsigned_abs() as usize
+                            + (y as i32 - j as i32).unsigned_abs() as usize
+                            <= radius
+                    });
+                    if near && group.tiles.contains(&human.target_tile) {
+                        *queues.entry(group.key()).or_default() += 1;
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[derive(Component, Debug, Clone)]
 pub struct Human {
     pub id: usize,
@@ -239,6 +483,7 @@ pub struct Human {
     pub next_position: Vec3,
     pub speed: f32,
     pub max_speed: f32,
+    pub free_speed: f32, //自由步行速度，max_speed乘上每个人的随机系数
     pub direction: Vec3,
     pub now_tile: (usize, usize),
     pub next_tile: (usize, usize),
@@ -250,6 +495,30 @@ pub struct Human {
     pub is_evacuated: bool,
     pub is_dead: bool,
     pub is_safe: bool,
+    pub use_elevator: bool, //疏散时优先乘电梯
+    pub elevator: Option<usize>, //正在等候或乘坐的电梯
+    pub riding: bool, //在电梯轿厢里
+    pub pre_movement: f32, //警报响起后开始疏散前的反应时间（秒）
+    pub familiarity: Familiarity,
+    pub behaviour: Behaviour,
+    pub carried_by: Option<usize>, //背着他的人
+    pub role: Role,
+    pub rescuing: Option<usize>, //员工正要去背或正背着的病人
+    pub profile: String, //人员类型，没有配置时为空
+    pub use_stairs: bool, //能走楼梯，不能的人只能乘电梯
+    pub heat_dose: f32, //累计的热剂量（FED）
+    pub fed: f32, //累计的窒息性气体剂量（FED），只在FED伤害模型下计算
+    pub incapacitated_at: Option<f32>, //总剂量达到失能阈值的时间
+    pub refuge_at: Option<f32>, //到达避难区开始避难的时间
+    pub released: bool, //已离开避难区继续向出口疏散，不再水平疏散
+}
+
+//人员对出口的熟悉程度：熟悉所有出口（如员工），或只知道主出口（如访客）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Familiarity {
+    #[default]
+    All,
+    MainOnly,
 }
 
 impl PartialEq for Human {
@@ -277,18 +546,52 @@ impl Human {
         self.next_position = Vec3::new(get_x(size, j, width), get_y(size, i, height), 12.0);
     }
 
-    pub fn find_my_target(&mut self, evacuation: &StoreyPass) {
-        let now = self.now_tile;
-        let mut min_dis = usize::MAX;
-        let mut temp_target = (0, 0);
-        for &(i, j) in &evacuation.exit {
-            let dis = ((i as i32 - now.0 as i32).abs() + (j as i32 - now.1 as i32).abs()) as usize;
-            if dis < min_dis {
-                min_dis = dis;
-                temp_target = (i, j);
+    //从start出发选择目标：按疏散场上的真实距离（已计入烟雾）、排队人数和熟悉程度打分，取分数最低的一组
+    //乘电梯的人有可达的电梯时只选电梯，否则只选楼梯或出口；访客有可达的主出口时只选主出口
+    //水平疏散时有可达的避难区就只选避难区
+    pub fn choose_target(
+        &self,
+        groups: &[TargetGroup],
+        queues: &HashMap<(usize, usize), usize>,
+        para: &NeededParameters,
+        start: (usize, usize),
+    ) -> Option<usize> {
+        let reachable = |group: &TargetGroup| group.field.cost[start.0][start.1] != usize::MAX;
+        let refuge = self.seeks_refuge(para) && groups.iter().any(|g| g.refuge && reachable(g));
+        let lift = self.use_elevator && groups.iter().any(|g| g.lift && reachable(g));
+        let main_only = self.familiarity == Familiarity::MainOnly
+            && groups.iter().any(|g| g.main && g.lift == lift && reachable(g));
+        //惊慌的人只顾走最近的出口，不看排队
+        let queue_weight = match self.behaviour {
+            Behaviour::Panicked => 0.0,
+            _ => para.queue_weight,
+        };
+        let mut best = None;
+        let mut best_score = f32::MAX;
+        for (n, group) in groups.iter().enumerate() {
+            let wanted = match refuge {
+                true => group.refuge,
+                false => {
+                    !group.refuge
+                        && group.lift == lift
+                        && (!main_only || group.main)
+                        && (!group.stair || self.use_stairs)
+                }
+            };
+            if !wanted || !reachable(group) {
+                continue;
+            }
+            let mut score = group.field.cost[start.0][start.1] as f32 / 100.0
+                + queue_weight * queues.get(&group.key()).copied().unwrap_or(0) as f32;
+            if group.tiles.contains(&self.target_tile) {
+                score -= para.route_switch_cost;
+            }
+            if score < best_score {
+                best_score = score;
+                best = Some(n);
             }
         }
-        self.target_tile = temp_target;
+        best
     }
 
     pub fn find_my_path(&mut self, evacuation: &StoreyPass) {
@@ -323,6 +626,11 @@ impl Human {
             }
         }
         let mut my_path = VecDeque::new();
+        //目标不可达时路径为空
+        if cost[target.0][target.1] == usize::MAX {
+            self.my_path = my_path;
+            return;
+        }
         let mut now = target;
         while now != self.now_tile {
             my_path.push_front(now);
@@ -386,6 +694,11 @@ impl Human {
             }
         }
         let mut my_path = VecDeque::new();
+        //目标不可达时路径为空
+        if cost[target.0][target.1] == usize::MAX {
+            self.my_path = my_path;
+            return;
+        }
         let mut now = target;
         while now != self.now_tile {
             my_path.push_front(now);
@@ -404,16 +717,95 @@ impl Human {
         }
     }
 
-    pub fn change_my_speed(&mut self) {
+    //速度随血量降低，再乘以密度、楼梯和烟雾的速度系数
+    pub fn change_my_speed(&mut self, factor: f32) {
         if self.hp <= 0.0 {
             self.speed = 0.0;
         } else {
-            self.speed = self.max_speed * self.hp / self.max_hp;
+            self.speed = self.free_speed * self.hp / self.max_hp * factor;
         }
     }
 
     pub fn change_my_direction(&mut self) {
-        self.direction = (self.next_position - self.position).normalize();
+        self.direction = (self.next_position - self.position).normalize_or_zero();
+    }
+
+    //以格为单位计算坐标，显示时乘以element_size即得到sprite的坐标
+    pub fn clc_position_in_storey(&mut self, rows: usize, cols: usize) {
+        let (width, height) = (cols as f32, rows as f32);
+        self.clc_position(1.0, width, height);
+        self.clc_next_position(1.0, width, height);
+        self.change_my_direction();
+    }
+
+    //在当前楼层选择目标并沿它的疏散场规划路径，没有可达的目标时原地不动
+    pub fn plan_to_exit(
+        &mut self,
+        pass: &StoreyPass,
+        evacuation: &BuildingEvacuation,
+        para: &NeededParameters,
+    ) {
+        self.target_tile = self.now_tile;
+        self.my_path.clear();
+        let groups = &evacuation.maps[&self.storey];
+        let queues = &evacuation.queues[&self.storey];
+        match self.choose_target(groups, queues, para, self.now_tile) {
+            Some(n) => {
+                if let Some(path) = groups[n].field.path_from(self.now_tile) {
+                    self.target_tile = *path.back().unwrap_or(&self.now_tile);
+                    self.my_path = path;
+                }
+            }
+            //不能走楼梯又没有可达的电梯时马上算被困，不等反应时间，好让员工先来背
+            None if !self.use_stairs
+                && matches!(self.behaviour, Behaviour::Normal | Behaviour::Panicked) =>
+            {
+                self.behaviour = Behaviour::Trapped
+            }
+            None => (),
+        }
+        //先站在原地，下一格有空位、门也能过时才在people_run里迈出去
+        self.next_tile = self.now_tile;
+        self.clc_position_in_storey(pass.map.len(), pass.map[0].len());
+    }
+
+    //疏散后寻找安全区和路径
+    pub fn plan_to_safe_place(&mut self, map: &Map) {
+        self.find_my_safe_place(map);
+        self.find_my_safe_path(map);
+        self.next_tile = *self.my_path.front().unwrap_or(&self.now_tile);
+        self.clc_position_in_storey(map.tiles.len(), map.tiles[0].len());
+    }
+
+    //重新选择目标并规划：先走到正在走向的格子，再沿所选目标的疏散场走
+    pub fn reroute(&mut self, evacuation: &BuildingEvacuation, para: &NeededParameters) {
+        let start = self.next_tile;
+        let groups = &evacuation.maps[&self.storey];
+        let queues = &evacuation.queues[&self.storey];
+        let Some(n) = self.choose_target(groups, queues, para, start) else {
+            return;
+        };
+        if let Some(mut path) = groups[n].field.path_from(start) {
+            if start != self.now_tile {
+                path.push_front(start);
+            }
+            if let Some(&target) = path.back() {
+                self.target_tile = target;
+                self.my_path = path;
+            }
+        }
+    }
+
+    //向下一个格子移动，不会越过下一个格子
+    pub fn walk(&mut self, time: f32) {
+        let newpos = self.position + self.direction * self.speed * time;
+        let dis0 = self.position.distance(newpos);
+        let dis1 = self.position.distance(self.next_position);
+        if dis0 >= dis1 {
+            self.position = self.next_position;
+        } else {
+            self.position = newpos;
+        }
     }
 }
 
@@ -424,7 +816,8 @@ impl Default for Human {
             position: Vec3::new(0.0, 0.0, 0.0),
             next_position: Vec3::new(0.0, 0.0, 0.0),
             speed: 0.0,
-            max_speed: 10.0,
+            max_speed: 1.5, //格/秒
+            free_speed: 1.5,
             direction: Vec3::new(0.0, 0.0, 0.0),
             now_tile: (0, 0),
             next_tile: (0, 0),
@@ -438,19 +831,39 @@ impl Default for Human {
             is_evacuated: false,
             is_dead: false,
             is_safe: false,
+            use_elevator: false,
+            elevator: None,
+            riding: false,
+            pre_movement: 0.0,
+            familiarity: Familiarity::All,
+            behaviour: Behaviour::Normal,
+            carried_by: None,
+            role: Role::Occupant,
+            rescuing: None,
+            profile: String::new(),
+            use_stairs: true,
+            heat_dose: 0.0,
+            fed: 0.0,
+            incapacitated_at: None,
+            refuge_at: None,
+            released: false,
         }
     }
 }
 
 impl Human{
-    pub fn rand_my_hp(&mut self, h: f32) {
-        let mut rng = rand::thread_rng();
-        self.hp = self.max_hp*rng.gen_range((1.0-h)..1.0);
+    pub fn rand_my_hp(&mut self, h: f32, rng: &mut StdRng) {
+        self.hp = self.max_hp*rng.gen_range((1.0-h)..=1.0);
+    }
+
+    //每个人的自由步行速度不同，max_speed不变
+    pub fn rand_my_speed(&mut self, v: f32, rng: &mut StdRng) {
+        self.free_speed = self.max_speed*rng.gen_range((1.0-v)..=(1.0+v));
+        self.speed = self.free_speed;
     }
 
-    pub fn rand_my_speed(&mut self, v: f32) {
-        let mut rng = rand::thread_rng();
-        self.speed = self.max_speed*rng.gen_range((1.0-v)..(1.0+v));
+    pub fn rand_my_pre_movement(&mut self, pre_movement: f32, r: f32, rng: &mut StdRng) {
+        self.pre_movement = pre_movement*rng.gen_range((1.0-r)..=(1.0+r));
     }
 }
 
@@ -458,6 +871,7 @@ impl Human{
 pub struct HumanMarker {
     pub id: usize,
     pub storey: MapMarker,
+    pub behaviour: Behaviour, //sprite当前显示的状态
 }
 
 impl HumanMarker {
@@ -465,6 +879,7 @@ impl HumanMarker {
         HumanMarker {
             id: human.id,
             storey: human.storey.clone(),
+            behaviour: human.behaviour,
         }
     }
 }
@@ -473,9 +888,6 @@ impl HumanMarker {
 pub struct TheCrowd {
     pub humans: Vec<Human>,
     pub texture_path: String,
-    pub element_size: f32,
-    pub board_width: f32,
-    pub board_height: f32,
 }
 
 impl TheCrowd {
@@ -483,22 +895,86 @@ impl TheCrowd {
         TheCrowd {
             humans: Vec::new(),
             texture_path: "./movethings/mango.png".to_string(),
-            element_size: 1.0,
-            board_width: 50.0,
-            board_height: 40.0,
         }
     }
 
-    pub fn extend_from_pass(&mut self, pass: &Map, storey: MapMarker,human_seed: f32,v: f32,h: f32) {
-        let mut rng = rand::thread_rng();
+    //在每一层随机生成人员
+    pub fn init_from_album(
+        &mut self,
+        map_album: &MapAlbum,
+        human_seed: f32,
+        v: f32,
+        h: f32,
+        rng: &mut StdRng,
+    ) {
+        for marker in sorted_markers(&map_album.maps) {
+            let map = &map_album.maps[&marker];
+            self.extend_from_pass(map, marker.clone(), human_seed, v, h, rng);
+        }
+    }
+
+    //为每个人寻找出口和路径
+    pub fn init_target_and_path(
+        &mut self,
+        building_pass: &BuildingPass,
+        evacuation: &BuildingEvacuation,
+        para: &NeededParameters,
+    ) {
+        for human in self.humans.iter_mut() {
+            let pass = &building_pass.maps[&human.storey];
+            human.plan_to_exit(pass, evacuation, para);
+        }
+    }
+
+    //随机选出一部分人作为只知道主出口的访客
+    pub fn assign_familiarity(&mut self, visitor_ratio: f32, rng: &mut StdRng) {
+        if visitor_ratio <= 0.0 {
+            return;
+        }
+        for human in self.humans.iter_mut() {
+            if rng.gen_bool(visitor_ratio.min(1.0) as f64) {
+                human.familiarity = Familiarity::MainOnly;
+            }
+        }
+    }
+
+    //随机选出一部分人乘电梯疏散
+    pub fn assign_elevator_users(&mut self, elevator_ratio: f32, rng: &mut StdRng) {
+        if elevator_ratio <= 0.0 {
+            return;
+        }
+        for human in self.humans.iter_mut() {
+            human.use_elevator = rng.gen_bool(elevator_ratio.min(1.0) as f64);
+        }
+    }
+
+    //为每个人生成反应时间，平均反应时间为0时所有人听到警报立即疏散
+    pub fn assign_pre_movement(&mut self, pre_movement: f32, r: f32, rng: &mut StdRng) {
+        if pre_movement <= 0.0 {
+            return;
+        }
+        for human in self.humans.iter_mut() {
+            human.rand_my_pre_movement(pre_movement, r, rng);
+        }
+    }
+
+    pub fn extend_from_pass(
+        &mut self,
+        pass: &Map,
+        storey: MapMarker,
+        human_seed: f32,
+        v: f32,
+        h: f32,
+        rng: &mut StdRng,
+    ) {
         for i in 0..pass.tiles.len() {
             for j in 0..pass.tiles[0].len() {
                 if TileType::Floor == pass.tiles[i][j] {
                     if rng.gen_bool(human_seed as f64) {
                         let mut human = Human::default();
                         human.id = self.humans.len();
-                        human.rand_my_hp(h);
-                        human.rand_my_speed(v);
+                        human.rand_my_hp(h, rng);
+                        human.rand_my_speed(v, rng);
                         human.now_tile = (i, j);
                         human.storey = storey.clone();
                         self.humans.push(human);
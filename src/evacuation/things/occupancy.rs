@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::components::painting::*;
+use crate::components::TileType;
+
+use super::human::Human;
+
+//计量通行流量的格子种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlowKind {
+    Door,
+    Exit,
+}
+
+//一个门格或出口格的实测流量
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TileFlow {
+    pub storey: String,
+    pub tile: (usize, usize),
+    pub kind: FlowKind,
+    pub passed: usize,
+    pub first: f32, //第一个人通过的时间
+    pub last: f32,  //最后一个人通过的时间
+}
+
+impl TileFlow {
+    //平均流量（人/秒），只有一个人通过时为0
+    pub fn rate(&self) -> f32 {
+        if self.passed > 1 && self.last > self.first {
+            (self.passed - 1) as f32 / (self.last - self.first)
+        } else {
+            0.0
+        }
+    }
+}
+
+//每一格上的人数，人算在他正走向（或站着）的格子上；另外记录门和出口的通行流量
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BuildingOccupancy {
+    pub maps: HashMap<MapMarker, Vec<Vec<usize>>>,
+    pub flows: BTreeMap<(MapMarker, (usize, usize)), TileFlow>,
+}
+
+impl BuildingOccupancy {
+    pub fn init_from_album(&mut self, map_album: &MapAlbum) {
+        for (marker, map) in map_album.maps.iter() {
+            let (rows, cols) = (map.tiles.len(), map.tiles[0].len());
+            self.maps.insert(marker.clone(), vec![vec![0; cols]; rows]);
+        }
+    }
+
+    //按所有还在楼内走动的人重新统计每一格的人数
+    pub fn count(&mut self, humans: &[Human]) {
+        for map in self.maps.values_mut() {
+            map.iter_mut().for_each(|row| row.fill(0));
+        }
+        for human in humans.iter() {
+            if human.is_dead || human.is_evacuated || human.riding {
+                continue;
+            }
+            self.enter(&human.storey, human.next_tile);
+        }
+    }
+
+    pub fn is_full(&self, storey: &MapMarker, tile: (usize, usize), capacity: usize) -> bool {
+        self.maps[storey][tile.0][tile.1] >= capacity
+    }
+
+    //以tile为中心3x3范围内的平均密度（人/平方米）
+    pub fn density(&self, storey: &MapMarker, tile: (usize, usize), tile_area: f32) -> f32 {
+        let map = &self.maps[storey];
+        let (i, j) = tile;
+        let rows = &map[i.saturating_sub(1)..(i + 2).min(map.len())];
+        let cols = j.saturating_sub(1)..(j + 2).min(map[0].len());
+        let people: usize = rows
+            .iter()
+            .map(|row| row[cols.clone()].iter().sum::<usize>())
+            .sum();
+        let cells = rows.len() * cols.len();
+        people as f32 / (cells as f32 * tile_area)
+    }
+
+    pub fn enter(&mut self, storey: &MapMarker, tile: (usize, usize)) {
+        self.maps.get_mut(storey).unwrap()[tile.0][tile.1] += 1;
+    }
+
+    pub fn leave(&mut self, storey: &MapMarker, tile: (usize, usize)) {
+        let count = &mut self.maps.get_mut(storey).unwrap()[tile.0][tile.1];
+        *count = count.saturating_sub(1);
+    }
+
+    //有人在time时走进这一格，门格计入流量
+    pub fn record_pass(
+        &mut self,
+        map_album: &MapAlbum,
+        storey: &MapMarker,
+        tile: (usize, usize),
+        time: f32,
+    ) {
+        if map_album.maps[storey].tiles[tile.0][tile.1] == TileType::Door {
+            self.record(storey, tile, FlowKind::Door, time);
+        }
+    }
+
+    //有人在time时从这个出口格疏散出去
+    pub fn record_exit(&mut self, storey: &MapMarker, tile: (usize, usize), time: f32) {
+        self.record(storey, tile, FlowKind::Exit, time);
+    }
+
+    fn record(&mut self, storey: &MapMarker, tile: (usize, usize), kind: FlowKind, time: f32) {
+        let flow = self
+            .flows
+            .entry((storey.clone(), tile))
+            .or_insert_with(|| TileFlow {
+                storey: storey.name.clone(),
+                tile,
+                kind,
+                passed: 0,
+                first: time,
+                last: time,
+            });
+        flow.passed += 1;
+        flow.last = time;
+    }
+
+    //按楼层和格子排序的流量记录
+    pub fn flows(&self) -> Vec<TileFlow> {
+        self.flows.values().cloned().collect()
+    }
+}
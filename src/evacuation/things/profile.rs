@@ -0,0 +1,176 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+
+use crate::components::painting::*;
+
+use super::behaviour::Behaviour;
+use super::human::*;
+use super::rescue::Role;
+
+//行动能力：能自己走、坐轮椅、卧床（不能自己行动，要员工背出去）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mobility {
+    #[default]
+    Ambulatory,
+    Wheelchair,
+    Bedridden,
+}
+
+//一类人员：速度（格/秒）、反应时间（秒）和血量都按mean*(1±spread)均匀分布
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OccupantProfile {
+    pub name: String,
+    #[serde(default)]
+    pub role: Role,
+    #[serde(default)]
+    pub mobility: Mobility,
+    pub speed: f32,
+    #[serde(default)]
+    pub speed_spread: f32,
+    #[serde(default = "default_stairs")]
+    pub stairs: bool, //能走楼梯，不能的人只能乘电梯
+    #[serde(default)]
+    pub pre_movement: f32,
+    #[serde(default)]
+    pub pre_movement_spread: f32,
+    pub hp: f32,
+    #[serde(default)]
+    pub hp_spread: f32,
+    #[serde(default)]
+    pub familiarity: Familiarity,
+}
+
+fn default_stairs() -> bool {
+    true
+}
+
+//人员类型配置，mix为地图集里没有设置的楼层和房间使用的比例
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct OccupantProfiles {
+    pub profiles: Vec<OccupantProfile>,
+    #[serde(default)]
+    pub mix: BTreeMap<String, f32>,
+}
+
+fn spread(mean: f32, r: f32, rng: &mut StdRng) -> f32 {
+    if r > 0.0 {
+        mean * rng.gen_range((1.0 - r)..=(1.0 + r))
+    } else {
+        mean
+    }
+}
+
+impl OccupantProfiles {
+    //配置在./assets/parameters/下
+    pub fn load(name: &str) -> Self {
+        let path = format!("./assets/parameters/{}.json", name);
+        let file = std::fs::File::open(path).unwrap();
+        serde_json::from_reader(std::io::BufReader::new(file)).unwrap()
+    }
+
+    pub fn save(&self, name: &str) {
+        let path = format!("./assets/parameters/{}.json", name);
+        let file = std::fs::File::create(path).unwrap();
+        serde_json::to_writer(std::io::BufWriter::new(file), self).unwrap();
+    }
+
+    //按比例随机抽一类人员，比例里写了配置中没有的名字时忽略
+    fn pick(&self, mix: &BTreeMap<String, f32>, rng: &mut StdRng) -> Option<&OccupantProfile> {
+        let weighted: Vec<(&OccupantProfile, f32)> = mix
+            .iter()
+            .filter_map(|(name, &w)| {
+                let profile = self.profiles.iter().find(|x| &x.name == name)?;
+                (w > 0.0).then_some((profile, w))
+            })
+            .collect();
+        let total: f32 = weighted.iter().map(|(_, w)| w).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut x = rng.gen_range(0.0..total);
+        for (profile, w) in weighted.iter() {
+            if x < *w {
+                return Some(profile);
+            }
+            x -= w;
+        }
+        weighted.last().map(|(profile, _)| *profile)
+    }
+}
+
+impl Human {
+    pub fn apply_profile(&mut self, profile: &OccupantProfile, rng: &mut StdRng) {
+        self.profile = profile.name.clone();
+        self.role = profile.role;
+        self.familiarity = profile.familiarity;
+        self.max_speed = profile.speed;
+        self.free_speed = spread(profile.speed, profile.speed_spread, rng);
+        self.speed = self.free_speed;
+        self.pre_movement = spread(profile.pre_movement, profile.pre_movement_spread, rng);
+        self.max_hp = profile.hp;
+        self.hp = self.max_hp * rng.gen_range((1.0 - profile.hp_spread.clamp(0.0, 1.0))..=1.0);
+        self.use_stairs = profile.stairs;
+        if !profile.stairs {
+            self.use_elevator = true;
+        }
+        self.behaviour = match profile.mobility {
+            Mobility::Bedridden => Behaviour::Incapacitated,
+            _ => Behaviour::Normal,
+        };
+    }
+}
+
+impl TheCrowd {
+    //按每个人所在房间或楼层的比例抽取人员类型，都没有设置时用配置里的比例，仍没有时保持原样
+    pub fn assign_profiles(
+        &mut self,
+        profiles: &OccupantProfiles,
+        map_album: &MapAlbum,
+        rng: &mut StdRng,
+    ) {
+        if profiles.profiles.is_empty() {
+            return;
+        }
+        for human in self.humans.iter_mut() {
+            let mix = map_album
+                .occupant_mix(&human.storey, human.now_tile)
+                .unwrap_or(&profiles.mix);
+            if let Some(profile) = profiles.pick(mix, rng) {
+                human.apply_profile(profile, rng);
+            }
+        }
+    }
+}
+
+//一类人员在一次模拟中的结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileOutcome {
+    pub name: String,
+    pub population: usize,
+    pub dead: usize,
+    pub evacuated: usize,
+}
+
+impl TheCrowd {
+    //按人员类型统计，没有配置人员类型时为空
+    pub fn profile_outcomes(&self) -> Vec<ProfileOutcome> {
+        let mut outcomes: BTreeMap<&str, ProfileOutcome> = BTreeMap::new();
+        for human in self.humans.iter().filter(|x| !x.profile.is_empty()) {
+            let outcome = outcomes
+                .entry(&human.profile)
+                .or_insert_with(|| ProfileOutcome {
+                    name: human.profile.clone(),
+                    population: 0,
+                    dead: 0,
+                    evacuated: 0,
+                });
+            outcome.population += 1;
+            outcome.dead += human.is_dead as usize;
+            outcome.evacuated += human.is_evacuated as usize;
+        }
+        outcomes.into_values().collect()
+    }
+}
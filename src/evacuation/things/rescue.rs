@@ -0,0 +1,375 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use std::collections::{HashMap, HashSet};
+
+use crate::components::painting::MapMarker;
+
+use super::behaviour::Behaviour;
+use super::human::*;
+use super::NeededParameters;
+
+//人员的角色：普通人员，或负责把不能行动的病人背出去的员工
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Role {
+    #[default]
+    Occupant,
+    Staff,
+}
+
+//一个病人被协助疏散的时间线
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RescueRecord {
+    pub patient: usize,
+    pub storey: String,
+    pub staff: Vec<usize>, //先后被派去的员工
+    pub assigned_at: f32,
+    pub picked_up_at: Option<f32>,
+    pub delivered_at: Option<f32>,
+    #[serde(default)]
+    pub sheltered_at: Option<f32>, //水平疏散时被送进避难区的时间
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BuildingRescue {
+    pub records: Vec<RescueRecord>,
+    pub open: HashMap<usize, usize>, //病人 -> 他的记录在records中的序号
+}
+
+//一步里派遣共用的信息：每个病人派去的员工、要人背的病人和以病人所在格为目标的疏散场
+//疏散场用到时才算，一步里每个病人只算一次，走不到的病人不会每个员工都重算一遍
+#[derive(Debug, Clone, Default)]
+pub struct RescuePlan {
+    rescuers: HashMap<usize, Vec<usize>>,
+    patients: Vec<usize>,
+    fields: HashMap<usize, StoreyEvacuation>,
+}
+
+impl RescuePlan {
+    pub fn new(crowd: &TheCrowd) -> Self {
+        let mut rescuers: HashMap<usize, Vec<usize>> = HashMap::new();
+        for human in crowd.humans.iter() {
+            if let (true, Some(patient)) = (human.is_rescuing(), human.rescuing) {
+                rescuers.entry(patient).or_default().push(human.id);
+            }
+        }
+        RescuePlan {
+            rescuers,
+            patients: crowd
+                .humans
+                .iter()
+                .filter(|x| x.needs_rescue())
+                .map(|x| x.id)
+                .collect(),
+            fields: HashMap::new(),
+        }
+    }
+
+    fn rescuers_of(&self, patient: usize) -> &[usize] {
+        self.rescuers.get(&patient).map_or(&[], |x| x.as_slice())
+    }
+}
+
+//以tile为唯一目标的疏散场，不计烟雾
+fn field_to(pass: &StoreyPass, tile: (usize, usize)) -> StoreyEvacuation {
+    let mut field = StoreyEvacuation::init_from_pass(pass);
+    field.update_from_pass(pass, &[tile], 0.0);
+    field
+}
+
+impl Human {
+    //从正在走向的格子出发，沿最短路走到tile，走不到时返回false
+    pub fn head_to(&mut self, tile: (usize, usize), pass: &StoreyPass) -> bool {
+        self.follow_to(tile, &field_to(pass, tile))
+    }
+
+    //沿以tile为目标的疏散场，从正在走向的格子走到tile
+    fn follow_to(&mut self, tile: (usize, usize), field: &StoreyEvacuation) -> bool {
+        let start = self.next_tile;
+        let Some(mut path) = field.path_from(start) else {
+            return false;
+        };
+        if start != self.now_tile {
+            path.push_front(start);
+        }
+        self.target_tile = tile;
+        self.my_path = path;
+        true
+    }
+
+    //去救人或正背着人的员工
+    pub fn is_rescuing(&self) -> bool {
+        self.role == Role::Staff && self.rescuing.is_some()
+    }
+
+    //还在楼内、能去救人的员工，在避难区里待着的员工也能出去救人
+    fn is_idle_staff(&self) -> bool {
+        self.role == Role::Staff
+            && self.rescuing.is_none()
+            && (self.behaviour == Behaviour::Normal || self.refuge_at.is_some())
+            && !self.is_dead
+            && !self.is_evacuated
+            && !self.riding
+            && self.elevator.is_none()
+    }
+
+    //要背着走、还没人背、也不在避难区里的病人
+    fn needs_rescue(&self) -> bool {
+        self.needs_carrying()
+            && self.carried_by.is_none()
+            && self.refuge_at.is_none()
+            && !self.is_dead
+            && !self.is_evacuated
+            && !self.riding
+    }
+}
+
+impl TheCrowd {
+    //随机选出一部分人作为员工
+    pub fn assign_staff(&mut self, staff_ratio: f32, rng: &mut StdRng) {
+        if staff_ratio <= 0.0 {
+            return;
+        }
+        for human in self.humans.iter_mut() {
+            if rng.gen_bool(staff_ratio.min(1.0) as f64) {
+                human.role = Role::Staff;
+            }
+        }
+    }
+
+    //随机选出一部分非员工作为卧床病人，从一开始就不能自己行动
+    pub fn assign_bedridden(&mut self, bedridden_ratio: f32, rng: &mut StdRng) {
+        if bedridden_ratio <= 0.0 {
+            return;
+        }
+        for human in self.humans.iter_mut() {
+            if human.role == Role::Occupant && rng.gen_bool(bedridden_ratio.min(1.0) as f64) {
+                human.behaviour = Behaviour::Incapacitated;
+            }
+        }
+    }
+
+    fn rescuers_of(&self, patient: usize) -> Vec<usize> {
+        self.humans
+            .iter()
+            .filter(|x| x.is_rescuing() && x.rescuing == Some(patient))
+            .map(|x| x.id)
+            .collect()
+    }
+}
+
+impl BuildingRescue {
+    //每一步：检查已有的派遣，人到齐了就背起病人，再给空闲的员工派新的病人
+    //started判断一个人是否已经开始疏散
+    pub fn update(
+        &mut self,
+        crowd: &mut TheCrowd,
+        pass: &BuildingPass,
+        evacuation: &BuildingEvacuation,
+        para: &NeededParameters,
+        time: f32,
+        started: impl Fn(&Human) -> bool,
+    ) {
+        self.check(crowd, pass, evacuation, para);
+        let mut plan = RescuePlan::new(crowd);
+        self.pick_up(crowd, pass, evacuation, para, time, &plan);
+        for id in 0..crowd.humans.len() {
+            if crowd.humans[id].is_idle_staff() && started(&crowd.humans[id]) {
+                self.assign(crowd, id, pass, para, time, &mut plan);
+            }
+        }
+    }
+
+    //员工倒下、惊慌或病人已不需要他（死亡、疏散或已在避难区里）时取消派遣；背病人的人倒下时其他员工回去重新背
+    fn check(
+        &mut self,
+        crowd: &mut TheCrowd,
+        pass: &BuildingPass,
+        evacuation: &BuildingEvacuation,
+        para: &NeededParameters,
+    ) {
+        for id in 0..crowd.humans.len() {
+            let Some(patient) = crowd.humans[id].rescuing else {
+                continue;
+            };
+            let staff = &crowd.humans[id];
+            let carrier = crowd.humans[patient].carried_by;
+            let lost = crowd.humans[patient].is_dead
+                || crowd.humans[patient].is_evacuated
+                || (crowd.humans[patient].refuge_at.is_some() && carrier.is_none())
+                || carrier.is_some_and(|x| crowd.humans[x].rescuing != Some(patient));
+            let able = !staff.is_dead
+                && !staff.is_evacuated
+                && matches!(staff.behaviour, Behaviour::Normal | Behaviour::Carrying(_));
+            let staff = &mut crowd.humans[id];
+            if !able {
+                staff.rescuing = None;
+            } else if lost {
+                staff.rescuing = None;
+                staff.behaviour = Behaviour::Normal;
+                staff.plan_to_exit(&pass.maps[&staff.storey], evacuation, para);
+            } else if carrier.is_none() && staff.behaviour == Behaviour::Carrying(patient) {
+                staff.behaviour = Behaviour::Normal;
+                let tile = crowd.humans[patient].now_tile;
+                let staff = &mut crowd.humans[id];
+                staff.head_to(tile, &pass.maps[&staff.storey]);
+            }
+        }
+    }
+
+    //派去的员工都站到病人所在格后，序号最小的背起病人，其他人一起护送
+    fn pick_up(
+        &mut self,
+        crowd: &mut TheCrowd,
+        pass: &BuildingPass,
+        evacuation: &BuildingEvacuation,
+        para: &NeededParameters,
+        time: f32,
+        plan: &RescuePlan,
+    ) {
+        let mut patients: Vec<usize> = self.open.keys().copied().collect();
+        patients.sort();
+        let staffed: HashSet<MapMarker> = crowd
+            .humans
+            .iter()
+            .filter(|x| x.is_idle_staff())
+            .map(|x| x.storey.clone())
+            .collect();
+        for patient in patients {
+            if !crowd.humans[patient].needs_rescue() {
+                continue;
+            }
+            let tile = crowd.humans[patient].now_tile;
+            let rescuers = plan.rescuers_of(patient);
+            let arrived = rescuers.iter().all(|&id| {
+                let staff = &crowd.humans[id];
+                staff.now_tile == tile && staff.next_tile == tile
+            });
+            //这一层已经没有空闲的员工能来时，到了的人先背
+            let no_more = !staffed.contains(&crowd.humans[patient].storey);
+            let enough = rescuers.len() >= para.staff_per_patient.max(1) || no_more;
+            if rescuers.is_empty() || !enough || !arrived {
+                continue;
+            }
+            crowd.humans[patient].carried_by = Some(rescuers[0]);
+            let released = crowd.humans[patient].released;
+            for &id in rescuers.iter() {
+                let staff = &mut crowd.humans[id];
+                staff.behaviour = Behaviour::Carrying(patient);
+                //已从避难区放行的病人直接背向出口，否则先背进避难区
+                staff.released = released;
+                staff.plan_to_exit(&pass.maps[&staff.storey], evacuation, para);
+            }
+            let record = &mut self.records[self.open[&patient]];
+            record.picked_up_at.get_or_insert(time);
+        }
+    }
+
+    //给员工派同一层离他最近、派去的人还不够的病人，返回是否派到了
+    pub fn assign(
+        &mut self,
+        crowd: &mut TheCrowd,
+        id: usize,
+        pass: &BuildingPass,
+        para: &NeededParameters,
+        time: f32,
+        plan: &mut RescuePlan,
+    ) -> bool {
+        let staff = &crowd.humans[id];
+        let (i, j) = staff.now_tile;
+        let storey_pass = &pass.maps[&staff.storey];
+        let mut candidates: Vec<(usize, usize)> = plan
+            .patients
+            .iter()
+            .map(|&patient| &crowd.humans[patient])
+            .filter(|x| {
+                x.storey == staff.storey
+                    && x.needs_rescue()
+                    && plan.rescuers_of(x.id).len() < para.staff_per_patient.max(1)
+            })
+            .map(|x| (x.now_tile.0.abs_diff(i) + x.now_tile.1.abs_diff(j), x.id))
+            .collect();
+        candidates.sort();
+        for (_, patient) in candidates {
+            let tile = crowd.humans[patient].now_tile;
+            let field = plan
+                .fields
+                .entry(patient)
+                .or_insert_with(|| field_to(storey_pass, tile));
+            let staff = &mut crowd.humans[id];
+            if !staff.follow_to(tile, field) {
+                continue;
+            }
+            plan.rescuers.entry(patient).or_default().push(id);
+            staff.rescuing = Some(patient);
+            staff.refuge_at = None;
+            if staff.behaviour == Behaviour::Sheltering {
+                staff.behaviour = Behaviour::Normal;
+            }
+            let storey = staff.storey.name.clone();
+            let n = *self.open.entry(patient).or_insert_with(|| {
+                self.records.push(RescueRecord {
+                    patient,
+                    storey,
+                    staff: Vec::new(),
+                    assigned_at: time,
+                    picked_up_at: None,
+                    delivered_at: None,
+                    sheltered_at: None,
+                });
+                self.records.len() - 1
+            });
+            self.records[n].staff.push(id);
+            return true;
+        }
+        false
+    }
+
+    //背着病人的员工到达出口，病人被送到安全处，一起护送的员工都空闲下来，返回是否送到
+    //先到出口的护送员工只是自己空闲下来
+    //refuge为真时送到的是避难区：病人留在那里避难，分阶段疏散时之后还要再背出去
+    pub fn deliver(
+        &mut self,
+        crowd: &mut TheCrowd,
+        staff: usize,
+        patient: usize,
+        time: f32,
+        refuge: bool,
+    ) -> bool {
+        let rescuers = match crowd.humans[patient].carried_by == Some(staff) {
+            true => crowd.rescuers_of(patient),
+            false => vec![staff],
+        };
+        for id in rescuers {
+            crowd.humans[id].rescuing = None;
+            crowd.humans[id].behaviour = Behaviour::Normal;
+        }
+        let human = &mut crowd.humans[patient];
+        if human.carried_by != Some(staff) {
+            return false;
+        }
+        human.carried_by = None;
+        if refuge {
+            human.refuge_at = Some(time);
+            if let Some(&n) = self.open.get(&patient) {
+                self.records[n].sheltered_at = Some(time);
+            }
+            return true;
+        }
+        human.is_evacuated = true;
+        human.is_safe = true;
+        human.behaviour = Behaviour::Sheltering;
+        if let Some(n) = self.open.remove(&patient) {
+            self.records[n].delivered_at = Some(time);
+        }
+        true
+    }
+
+    pub fn rescued_num(&self) -> usize {
+        self.records
+            .iter()
+            .filter(|x| x.delivered_at.is_some())
+            .count()
+    }
+}
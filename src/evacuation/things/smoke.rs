@@ -1,80 +1,118 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::components::painting::*;
 use crate::components::TileType;
 
+use super::NeededParameters;
+
 use std::{collections::HashMap, vec};
 
+//每一层的烟雾，produced和vented记录这一层累计产生和排出楼外的烟雾量，
+//transferred为经楼梯和竖井净流入这一层的量，produced-vented+transferred等于这一层现有的烟雾总量
 #[derive(Component, Debug, Clone, PartialEq)]
 pub struct StoreySmoke {
     pub map: Vec<Vec<Smoke>>,
+    pub produced: f32,
+    pub vented: f32,
+    pub transferred: f32,
 }
 
 impl StoreySmoke {
     pub fn new(rows: usize, cols: usize) -> Self {
         StoreySmoke {
             map: vec![vec![Smoke::new_diffusible(); cols]; rows],
+            produced: 0.0,
+            vented: 0.0,
+            transferred: 0.0,
         }
     }
 
     pub fn init_from_map(&mut self, map: &Map) {
         for i in 0..map.tiles.len() {
             for j in 0..map.tiles[0].len() {
-                if map.tiles[i][j] == TileType::Wall || map.tiles[i][j] == TileType::Exit {
+                if map.tiles[i][j] == TileType::Wall || map.tiles[i][j] == TileType::Black {
                     self.map[i][j] = Smoke::InDiffusible;
+                } else if map.tiles[i][j] == TileType::Exit {
+                    self.map[i][j] = Smoke::Vent;
                 }
             }
         }
     }
 
-    pub fn diffuse(&mut self) {
+    //在一格上产生烟雾，只有能扩散的格才接受新烟
+    pub fn add(&mut self, tile: (usize, usize), value: f32) {
+        if let Smoke::Diffusible(density) = &mut self.map[tile.0][tile.1] {
+            density.density_up(value);
+            self.produced += value;
+        }
+    }
+
+    //烟从别的楼层流进（value为正）或流出（value为负）这一格
+    pub fn transfer(&mut self, tile: (usize, usize), value: f32) {
+        if let Smoke::Diffusible(density) = &mut self.map[tile.0][tile.1] {
+            density.density_up(value);
+            self.transferred += value;
+        }
+    }
+
+    //这一层现有的烟雾总量
+    pub fn mass(&self) -> f32 {
+        self.map
+            .iter()
+            .flat_map(|row| row.iter())
+            .map(|x| x.get_density())
+            .sum()
+    }
+
+    //显式有限体积格式：每条相邻两格的边上按浓度差扩散、按迎风格式随气流平移，
+    //一格流出的量就是另一格流入的量，烟雾总量守恒，流进排烟口的烟记为排出；
+    //时间步超过稳定性限制时拆成几个子步，结果与遍历顺序和帧率无关
+    pub fn diffuse(&mut self, dt: f32, diffusion: f32, drift: [f32; 2]) {
+        let rate = 4.0 * diffusion.max(0.0) + drift[0].abs() + drift[1].abs();
+        let substeps = ((rate * dt / 0.9).ceil() as usize).max(1);
+        let h = dt / substeps as f32;
+        for _ in 0..substeps {
+            self.diffuse_once(h, diffusion.max(0.0), drift);
+        }
+    }
+
+    fn diffuse_once(&mut self, h: f32, diffusion: f32, drift: [f32; 2]) {
         let rows = self.map.len();
         let cols = self.map[0].len();
+        let old: Vec<Vec<f32>> = self
+            .map
+            .iter()
+            .map(|row| row.iter().map(|x| x.get_density()).collect())
+            .collect();
+        let mut change = vec![vec![0.0; cols]; rows];
         for i in 0..rows {
             for j in 0..cols {
-                let mut sum = 0.0;
-                let mut count = 0.0;
-                let mut vec = Vec::new();
-                if let Smoke::Diffusible(density) = &mut self.map[i][j] {
-                    vec.push((i, j));
-                    sum += density.value;
-                    count += 1.0;
-                } else {
+                if !self.map[i][j].is_open() {
                     continue;
                 }
-                if i > 0 {
-                    if let Smoke::Diffusible(density) = &mut self.map[i - 1][j] {
-                        vec.push((i - 1, j));
-                        sum += density.value;
-                        count += 1.0;
+                //只算向下和向右的两条边，每条边只算一次
+                for (ni, nj, velocity) in [(i + 1, j, drift[0]), (i, j + 1, drift[1])] {
+                    if ni >= rows || nj >= cols || !self.map[ni][nj].is_open() {
+                        continue;
                     }
+                    let upwind = if velocity >= 0.0 {
+                        old[i][j]
+                    } else {
+                        old[ni][nj]
+                    };
+                    let flux = (diffusion * (old[i][j] - old[ni][nj]) + velocity * upwind) * h;
+                    change[i][j] -= flux;
+                    change[ni][nj] += flux;
                 }
-                if i < rows - 1 {
-                    if let Smoke::Diffusible(density) = &mut self.map[i + 1][j] {
-                        vec.push((i + 1, j));
-                        sum += density.value;
-                        count += 1.0;
-                    }
-                }
-                if j > 0 {
-                    if let Smoke::Diffusible(density) = &mut self.map[i][j - 1] {
-                        vec.push((i, j - 1));
-                        sum += density.value;
-                        count += 1.0;
-                    }
-                }
-                if j < cols - 1 {
-                    if let Smoke::Diffusible(density) = &mut self.map[i][j + 1] {
-                        vec.push((i, j + 1));
-                        sum += density.value;
-                        count += 1.0;
-                    }
-                }
-                let average = sum / count;
-                for (x, y) in vec.iter() {
-                    if let Smoke::Diffusible(density) = &mut self.map[*x][*y] {
-                        density.value = average;
-                    }
+            }
+        }
+        for (row, change_row) in self.map.iter_mut().zip(change.iter()) {
+            for (smoke, change) in row.iter_mut().zip(change_row.iter()) {
+                match smoke {
+                    Smoke::Diffusible(density) => density.value += change,
+                    Smoke::Vent => self.vented += change,
+                    _ => {}
                 }
             }
         }
@@ -85,6 +123,8 @@ impl StoreySmoke {
 pub enum Smoke {
     Diffusible(Density),
     InDiffusible,
+    Closed(Density), //关着的门，保留已有的烟但不参与扩散
+    Vent,            //出口和排烟口，流到这里的烟排出楼外，本身不存烟
 }
 
 impl Smoke {
@@ -99,10 +139,23 @@ impl Smoke {
     }
 
     pub fn get_density(&self) -> f32 {
-        if let Smoke::Diffusible(density) = self {
-            density.value
-        } else {
-            0.0
+        match self {
+            Smoke::Diffusible(density) | Smoke::Closed(density) => density.value,
+            Smoke::InDiffusible | Smoke::Vent => 0.0,
+        }
+    }
+
+    //烟能流进流出的格
+    pub fn is_open(&self) -> bool {
+        matches!(self, Smoke::Diffusible(_) | Smoke::Vent)
+    }
+
+    //关门时烟不再经过这一格，开门后恢复扩散
+    pub fn set_closed(&mut self, closed: bool) {
+        match self {
+            Smoke::Diffusible(density) if closed => *self = Smoke::Closed(density.clone()),
+            Smoke::Closed(density) if !closed => *self = Smoke::Diffusible(density.clone()),
+            _ => {}
         }
     }
 }
@@ -153,3 +206,45 @@ impl Default for BuildingSmoke {
         }
     }
 }
+
+impl BuildingSmoke {
+    //根据地图初始化每一层的烟雾，再把排烟口所在的格设为排烟格
+    pub fn init_from_album(&mut self, map_album: &MapAlbum) {
+        for (marker, map) in map_album.maps.iter() {
+            let mut storey_smoke = StoreySmoke::new(map.tiles.len(), map.tiles[0].len());
+            storey_smoke.init_from_map(map);
+            self.maps.insert(marker.clone(), storey_smoke);
+        }
+        for vent in map_album.vents.iter() {
+            if let Some(storey_smoke) = self.maps.get_mut(&vent.storey) {
+                storey_smoke.map[vent.tile.0][vent.tile.1] = Smoke::Vent;
+            }
+        }
+    }
+
+    //烟雾扩散
+    pub fn diffuse(&mut self, dt: f32, para: &NeededParameters) {
+        for (_, storey_smoke) in self.maps.iter_mut() {
+            storey_smoke.diffuse(dt, para.smoke_diffusion, para.smoke_drift);
+        }
+    }
+
+    //全楼累计产生、排出和现有的烟雾量，按楼层名的顺序累加，同一种子的结果逐位相同
+    pub fn balance(&self) -> SmokeBalance {
+        let mut balance = SmokeBalance::default();
+        for marker in sorted_markers(&self.maps) {
+            let storey_smoke = &self.maps[&marker];
+            balance.produced += storey_smoke.produced;
+            balance.vented += storey_smoke.vented;
+            balance.remaining += storey_smoke.mass();
+        }
+        balance
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SmokeBalance {
+    pub produced: f32,
+    pub vented: f32,
+    pub remaining: f32,
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+use super::NeededParameters;
+
+//步行速度与人群密度的关系（基本图）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FundamentalDiagram {
+    None, //不受密度影响
+    #[default]
+    Weidmann,
+}
+
+//所在楼梯段的方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StairWay {
+    Up,
+    Down,
+}
+
+impl FundamentalDiagram {
+    //密度为density（人/平方米）时的速度与自由步行速度之比
+    //Weidmann: v = v0 * (1 - exp(-γ * (1/ρ - 1/ρmax)))
+    pub fn factor(&self, density: f32, para: &NeededParameters) -> f32 {
+        match self {
+            FundamentalDiagram::None => 1.0,
+            FundamentalDiagram::Weidmann => {
+                if density <= 0.0 {
+                    return 1.0;
+                }
+                let x = 1.0 / density - 1.0 / para.jam_density;
+                1.0 - (-para.weidmann_gamma * x).exp()
+            }
+        }
+    }
+}
+
+//密度、楼梯和烟雾对速度的综合影响，不低于min_speed_factor，避免人群完全停住
+pub fn speed_factor(
+    density: f32,
+    stair: Option<StairWay>,
+    smoke: f32,
+    para: &NeededParameters,
+) -> f32 {
+    let crowd = para.fundamental_diagram.factor(density, para);
+    let stair = match stair {
+        Some(StairWay::Up) => para.stair_up_factor,
+        Some(StairWay::Down) => para.stair_down_factor,
+        None => 1.0,
+    };
+    //烟雾降低能见度，浓度每增加1速度降低smoke_speed_reduction
+    let smoke = 1.0 - para.smoke_speed_reduction * smoke;
+    (crowd * stair * smoke).clamp(para.min_speed_factor, 1.0)
+}
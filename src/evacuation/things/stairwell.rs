@@ -0,0 +1,263 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::components::painting::*;
+use crate::components::TileType;
+
+use super::smoke::{BuildingSmoke, Smoke};
+use super::NeededParameters;
+
+//某一层上的一格
+type Cell = (MapMarker, (usize, usize));
+
+//烟雾的竖向通道：lower层的一格与upper层的一格相连，lower格的烟每秒按rate的比例升到upper格
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmokeLink {
+    pub lower: Cell,
+    pub upper: Cell,
+    pub rate: f32,
+}
+
+//某一时刻梯间的烟雾
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StairwellSample {
+    pub time: f32,
+    pub mean: f32, //梯间各格烟雾浓度的平均值
+    pub max: f32,  //梯间各格烟雾浓度的最大值
+}
+
+//一部楼梯：各层上相连的楼梯格被楼梯连接串起来，名字取最低一层上第一个楼梯格
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stairwell {
+    pub name: String,
+    pub tiles: Vec<Cell>,
+    pub contaminated_at: Option<f32>,
+    pub peak: f32,
+    pub samples: Vec<StairwellSample>,
+}
+
+impl Stairwell {
+    fn sample(&self, smoke: &BuildingSmoke, time: f32) -> StairwellSample {
+        let densities: Vec<f32> = self
+            .tiles
+            .iter()
+            .map(|(marker, (i, j))| smoke.maps[marker].map[*i][*j].get_density())
+            .collect();
+        StairwellSample {
+            time,
+            mean: densities.iter().sum::<f32>() / densities.len() as f32,
+            max: densities.iter().fold(0.0, |a, &b| a.max(b)),
+        }
+    }
+}
+
+//每部楼梯结束时的状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StairwellOutcome {
+    pub name: String,
+    pub storeys: Vec<String>,
+    pub contaminated_at: Option<f32>, //梯间烟雾浓度第一次达到stair_smoke_limit的时间
+    pub peak: f32,                    //梯间出现过的最大烟雾浓度
+    pub samples: Vec<StairwellSample>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BuildingStairwells {
+    pub links: Vec<SmokeLink>,
+    pub stairwells: Vec<Stairwell>,
+    pub next_sample: f32,
+}
+
+impl BuildingStairwells {
+    //楼梯连接、电梯井和竖井相邻两层的开口都是烟雾的竖向通道
+    pub fn init_from_album(&mut self, map_album: &MapAlbum, para: &NeededParameters) {
+        for link in map_album.stairs.iter() {
+            self.link(map_album, &link.lower, &link.upper, para.stair_smoke_rate);
+        }
+        for shaft in map_album.elevators.iter() {
+            self.link_column(map_album, &shaft.stops, para.shaft_smoke_rate);
+        }
+        for shaft in map_album.shafts.iter() {
+            let rate = shaft.rate.unwrap_or(para.shaft_smoke_rate);
+            self.link_column(map_album, &shaft.openings, rate);
+        }
+        self.stairwells = find_stairwells(map_album);
+    }
+
+    fn link(&mut self, map_album: &MapAlbum, lower: &StairEnd, upper: &StairEnd, rate: f32) {
+        let inside = |end: &StairEnd| {
+            map_album
+                .maps
+                .get(&end.storey)
+                .is_some_and(|map| end.tile.0 < map.tiles.len() && end.tile.1 < map.tiles[0].len())
+        };
+        if !inside(lower) || !inside(upper) {
+            return;
+        }
+        self.links.push(SmokeLink {
+            lower: (lower.storey.clone(), lower.tile),
+            upper: (upper.storey.clone(), upper.tile),
+            rate,
+        });
+    }
+
+    //按楼层从下往上把相邻两层的开口连起来
+    fn link_column(&mut self, map_album: &MapAlbum, ends: &[StairEnd], rate: f32) {
+        let mut ends: Vec<&StairEnd> = ends.iter().collect();
+        ends.sort_by_key(|end| map_album.storey_info(&end.storey).index);
+        for pair in ends.windows(2) {
+            self.link(map_album, pair[0], pair[1], rate);
+        }
+    }
+
+    //烟沿竖向通道从下层升到上层，升上去的量从下层那一格扣掉，总量守恒
+    //各通道的流量都按这一步开始时的浓度计算，与通道的先后顺序无关
+    pub fn transfer(&self, smoke: &mut BuildingSmoke, dt: f32) {
+        let flows: Vec<f32> = self
+            .links
+            .iter()
+            .map(|link| {
+                let (lower, (i, j)) = &link.lower;
+                let (upper, (ui, uj)) = &link.upper;
+                let from = &smoke.maps[lower].map[*i][*j];
+                let to = &smoke.maps[upper].map[*ui][*uj];
+                match (from, to) {
+                    (Smoke::Diffusible(density), Smoke::Diffusible(_)) => {
+                        density.value * (1.0 - (-link.rate.max(0.0) * dt).exp())
+                    }
+                    _ => 0.0,
+                }
+            })
+            .collect();
+        for (link, flow) in self.links.iter().zip(flows) {
+            if flow <= 0.0 {
+                continue;
+            }
+            let (lower, tile) = &link.lower;
+            smoke.maps.get_mut(lower).unwrap().transfer(*tile, -flow);
+            let (upper, tile) = &link.upper;
+            smoke.maps.get_mut(upper).unwrap().transfer(*tile, flow);
+        }
+    }
+
+    //每一步检查梯间是否被烟污染，每隔stairwell_sample_interval秒记录一次梯间的烟雾
+    pub fn record(&mut self, smoke: &BuildingSmoke, para: &NeededParameters, time: f32) {
+        let sampling = time >= self.next_sample;
+        if sampling {
+            self.next_sample = time + para.stairwell_sample_interval;
+        }
+        for stairwell in self.stairwells.iter_mut() {
+            let sample = stairwell.sample(smoke, time);
+            stairwell.peak = stairwell.peak.max(sample.max);
+            if stairwell.contaminated_at.is_none() && sample.max >= para.stair_smoke_limit {
+                stairwell.contaminated_at = Some(time);
+            }
+            if sampling {
+                stairwell.samples.push(sample);
+            }
+        }
+    }
+
+    pub fn outcomes(&self) -> Vec<StairwellOutcome> {
+        self.stairwells
+            .iter()
+            .map(|stairwell| {
+                let mut storeys: Vec<String> = Vec::new();
+                for (marker, _) in stairwell.tiles.iter() {
+                    if !storeys.contains(&marker.name) {
+                        storeys.push(marker.name.clone());
+                    }
+                }
+                StairwellOutcome {
+                    name: stairwell.name.clone(),
+                    storeys,
+                    contaminated_at: stairwell.contaminated_at,
+                    peak: stairwell.peak,
+                    samples: stairwell.samples.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+//每层上相连的楼梯格为一块，楼梯连接把上下两层的块并成一部楼梯，没有连接的块不算楼梯间
+fn find_stairwells(map_album: &MapAlbum) -> Vec<Stairwell> {
+    let mut region_of: HashMap<Cell, usize> = HashMap::new();
+    let mut regions: Vec<Vec<Cell>> = Vec::new();
+    for marker in sorted_markers(&map_album.maps) {
+        let map = &map_album.maps[&marker];
+        let rows = map.tiles.len();
+        let cols = map.tiles[0].len();
+        for i in 0..rows {
+            for j in 0..cols {
+                if map.tiles[i][j] != TileType::Stair
+                    || region_of.contains_key(&(marker.clone(), (i, j)))
+                {
+                    continue;
+                }
+                let mut region = Vec::new();
+                region_of.insert((marker.clone(), (i, j)), regions.len());
+                let mut queue = VecDeque::from([(i, j)]);
+                while let Some((x, y)) = queue.pop_front() {
+                    region.push((marker.clone(), (x, y)));
+                    for (dx, dy) in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+                        let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                        if nx < 0 || ny < 0 || nx >= rows as i32 || ny >= cols as i32 {
+                            continue;
+                        }
+                        let (nx, ny) = (nx as usize, ny as usize);
+                        let cell = (marker.clone(), (nx, ny));
+                        if map.tiles[nx][ny] == TileType::Stair && !region_of.contains_key(&cell) {
+                            region_of.insert(cell, regions.len());
+                            queue.push_back((nx, ny));
+                        }
+                    }
+                }
+                regions.push(region);
+            }
+        }
+    }
+
+    fn root(parent: &mut [usize], mut x: usize) -> usize {
+        while parent[x] != x {
+            parent[x] = parent[parent[x]];
+            x = parent[x];
+        }
+        x
+    }
+    let mut parent: Vec<usize> = (0..regions.len()).collect();
+    let mut linked = vec![false; regions.len()];
+    for link in map_album.stairs.iter() {
+        let upper = region_of.get(&(link.upper.storey.clone(), link.upper.tile));
+        let lower = region_of.get(&(link.lower.storey.clone(), link.lower.tile));
+        if let (Some(&a), Some(&b)) = (upper, lower) {
+            linked[a] = true;
+            linked[b] = true;
+            let (a, b) = (root(&mut parent, a), root(&mut parent, b));
+            parent[a.max(b)] = a.min(b);
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<Cell>> = BTreeMap::new();
+    for (n, region) in regions.into_iter().enumerate() {
+        if linked[n] {
+            let r = root(&mut parent, n);
+            groups.entry(r).or_default().extend(region);
+        }
+    }
+    groups
+        .into_values()
+        .map(|mut tiles| {
+            tiles.sort_by_key(|(marker, tile)| (map_album.storey_info(marker).index, *tile));
+            let (marker, (i, j)) = &tiles[0];
+            Stairwell {
+                name: format!("{}({}, {})", marker.name, i, j),
+                tiles,
+                contaminated_at: None,
+                peak: 0.0,
+                samples: Vec::new(),
+            }
+        })
+        .collect()
+}
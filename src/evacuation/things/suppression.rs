@@ -0,0 +1,144 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::components::painting::*;
+use crate::components::TileType;
+
+use super::fire::{BuildingFire, Fire, StoreyFire};
+use super::smoke::BuildingSmoke;
+use super::NeededParameters;
+
+//喷头状态：待命、已触发（剩余延迟）、正在喷水
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeadState {
+    Standby,
+    Triggered(f32),
+    Active,
+}
+
+//一个喷头（地图上的消火栓格）：受热或烟雾达到阈值后触发，延迟后在有效半径内喷水
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sprinkler {
+    pub storey: MapMarker,
+    pub tile: (usize, usize),
+    pub state: HeadState,
+    pub activated_at: Option<f32>,
+    pub extinguished: usize,
+    pub covered: Vec<(usize, usize)>, //开始喷水时确定的有效范围
+}
+
+impl Sprinkler {
+    fn is_triggered(
+        &self,
+        fire: &BuildingFire,
+        smoke: &BuildingSmoke,
+        para: &NeededParameters,
+    ) -> bool {
+        let (i, j) = self.tile;
+        let map = &fire.maps[&self.storey].map;
+        let r = para.sprinkler_heat_radius;
+        let heat = (i.saturating_sub(r)..(i + r + 1).min(map.len())).any(|x| {
+            (j.saturating_sub(r)..(j + r + 1).min(map[0].len()))
+                .any(|y| matches!(map[x][y], Fire::On(_)))
+        });
+        heat || smoke.maps[&self.storey].map[i][j].get_density() >= para.sprinkler_smoke_threshold
+    }
+
+    //有效半径内、不隔着墙和关着的门的格，与火势蔓延用同样的方式绕开挡火格
+    fn coverage(&self, storey_fire: &StoreyFire, radius: f32) -> Vec<(usize, usize)> {
+        let (i, j) = self.tile;
+        let in_range = |(x, y): (usize, usize)| {
+            (x.abs_diff(i).pow(2) + y.abs_diff(j).pow(2)) as f32 <= radius * radius
+        };
+        let mut seen = vec![vec![0; storey_fire.map[0].len()]; storey_fire.map.len()];
+        storey_fire.flood(self.tile, in_range, &mut seen, 1).0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BuildingSuppression {
+    pub heads: Vec<Sprinkler>,
+}
+
+impl BuildingSuppression {
+    //不启用喷淋时没有喷头
+    pub fn init_from_album(&mut self, map_album: &MapAlbum, para: &NeededParameters) {
+        if !para.suppression {
+            return;
+        }
+        for marker in sorted_markers(&map_album.maps) {
+            let map = &map_album.maps[&marker];
+            for i in 0..map.tiles.len() {
+                for j in 0..map.tiles[0].len() {
+                    if map.tiles[i][j] == TileType::Hydrant {
+                        self.heads.push(Sprinkler {
+                            storey: marker.clone(),
+                            tile: (i, j),
+                            state: HeadState::Standby,
+                            activated_at: None,
+                            extinguished: 0,
+                            covered: Vec::new(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    //推进dt秒：触发计时，喷水的喷头打湿有效范围，并按概率扑灭范围内的火
+    pub fn update(
+        &mut self,
+        dt: f32,
+        time: f32,
+        fire: &mut BuildingFire,
+        smoke: &BuildingSmoke,
+        para: &NeededParameters,
+        rng: &mut StdRng,
+    ) {
+        let p = 1.0 - (-para.sprinkler_extinguish_rate * dt).exp();
+        for head in self.heads.iter_mut() {
+            match head.state {
+                HeadState::Standby => {
+                    if head.is_triggered(fire, smoke, para) {
+                        head.state = HeadState::Triggered(para.sprinkler_delay);
+                    }
+                }
+                HeadState::Triggered(delay) => {
+                    if delay > dt {
+                        head.state = HeadState::Triggered(delay - dt);
+                    } else {
+                        head.state = HeadState::Active;
+                        head.activated_at = Some(time);
+                        let storey_fire = fire.maps.get_mut(&head.storey).unwrap();
+                        head.covered = head.coverage(storey_fire, para.sprinkler_radius);
+                        for &(x, y) in head.covered.iter() {
+                            storey_fire.wet[x][y] = true;
+                        }
+                    }
+                }
+                HeadState::Active => {
+                    let storey_fire = fire.maps.get_mut(&head.storey).unwrap();
+                    for &(x, y) in head.covered.iter() {
+                        if let Fire::On(_) = storey_fire.map[x][y] {
+                            if rng.gen_bool(p.clamp(0.0, 1.0) as f64) {
+                                storey_fire.extinguish((x, y));
+                                head.extinguished += 1;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn activated_num(&self) -> usize {
+        self.heads
+            .iter()
+            .filter(|x| x.activated_at.is_some())
+            .count()
+    }
+
+    pub fn extinguished_num(&self) -> usize {
+        self.heads.iter().map(|x| x.extinguished).sum()
+    }
+}
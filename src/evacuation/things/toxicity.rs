@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+
+use super::heat::{heat_dose_rate, StoreyHeat};
+use super::human::{Human, TheCrowd};
+use super::smoke::StoreySmoke;
+use super::NeededParameters;
+
+//烟气伤害模型：按烟雾浓度直接扣血（Simple），
+//或按ISO 13571的有效剂量分数累计CO、CO2、缺氧和高温的剂量（Fed）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ToxicityModel {
+    #[default]
+    Simple,
+    Fed,
+}
+
+//一格烟雾中的气体成分，按烟雾浓度和每单位浓度的产率折算
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gases {
+    pub co: f32,  //CO（ppm）
+    pub co2: f32, //CO2（%）
+    pub o2: f32,  //O2（%）
+}
+
+impl Gases {
+    pub fn from_density(density: f32, para: &NeededParameters) -> Self {
+        let density = density.max(0.0);
+        Gases {
+            co: para.co_yield * density,
+            co2: 0.04 + para.co2_yield * density,
+            o2: (20.9 - para.o2_depletion * density).max(0.0),
+        }
+    }
+
+    //窒息性气体每秒的剂量：CO按φ/35000每分钟累计并乘以CO2引起的换气加快系数exp(φ/5)，
+    //缺氧按Purser的1/exp(8.13-0.54(20.9-φ))每分钟累计
+    pub fn dose_rate(&self) -> f32 {
+        let co = self.co / 35000.0 * (self.co2 / 5.0).exp();
+        let o2 = match self.o2 < 20.9 {
+            true => 1.0 / (8.13 - 0.54 * (20.9 - self.o2)).exp(),
+            false => 0.0,
+        };
+        (co + o2) / 60.0
+    }
+}
+
+//每个人累计的剂量
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentDose {
+    pub id: usize,
+    pub fed: f32,                      //窒息性气体的剂量
+    pub heat: f32,                     //热剂量
+    pub incapacitated_at: Option<f32>, //总剂量达到fed_incapacitation的时间
+    pub dead: bool,
+}
+
+impl Human {
+    //两种剂量之和
+    pub fn total_dose(&self) -> f32 {
+        self.fed + self.heat_dose
+    }
+
+    //累计所在格的剂量，总剂量达到fed_incapacitation时失去行动能力，达到fed_death时死亡；
+    //失能和死亡只由剂量决定，与初始血量无关，血量只在死亡时清零
+    pub fn fed_damage(
+        &mut self,
+        smoke: &StoreySmoke,
+        heat: &StoreyHeat,
+        para: &NeededParameters,
+        dt: f32,
+        time: f32,
+    ) {
+        let (i, j) = self.now_tile;
+        let gases = Gases::from_density(smoke.map[i][j].get_density(), para);
+        let fed = gases.dose_rate() * dt;
+        let heat_dose = match para.heat_damage {
+            true => heat_dose_rate(heat.temperature[i][j], heat.radiation[i][j], para) * dt,
+            false => 0.0,
+        };
+        self.fed += fed;
+        self.heat_dose += heat_dose;
+        if self.incapacitated_at.is_none() && self.total_dose() >= para.fed_incapacitation {
+            self.incapacitated_at = Some(time);
+        }
+        if self.total_dose() >= para.fed_death {
+            self.hp = 0.0;
+            self.is_dead = true;
+        }
+    }
+}
+
+impl TheCrowd {
+    pub fn doses(&self) -> Vec<AgentDose> {
+        self.humans
+            .iter()
+            .map(|human| AgentDose {
+                id: human.id,
+                fed: human.fed,
+                heat: human.heat_dose,
+                incapacitated_at: human.incapacitated_at,
+                dead: human.is_dead,
+            })
+            .collect()
+    }
+}
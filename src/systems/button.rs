@@ -233,9 +233,26 @@ pub fn load_map_album(
     if let Some(command) = atomic_command.queue.front() {
         if command == &AtomicCommand::LoadMapAlbum {
             let name = pipe.input.pop_front().unwrap();
-            let album = MapAlbum::load(&name);
+            let album = match MapAlbum::load(&name) {
+                Ok(album) => album,
+                Err(e) => {
+                    //地图集有错误时不加载，把错误显示在终端里
+                    pipe.output.push_back(e);
+                    atomic_command.queue.pop_front();
+                    return;
+                }
+            };
             map_album.maps = album.maps;
             map_album.now_map = album.now_map;
+            map_album.stairs = album.stairs;
+            map_album.storeys = album.storeys;
+            map_album.elevators = album.elevators;
+            map_album.doors = album.doors;
+            map_album.exits = album.exits;
+            map_album.occupants = album.occupants;
+            map_album.compartments = album.compartments;
+            map_album.vents = album.vents;
+            map_album.shafts = album.shafts;
             atomic_command.queue.extend(
                 vec![
                     AtomicCommand::NewPaperAlbum,
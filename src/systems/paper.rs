@@ -3,8 +3,6 @@ use bevy::input::mouse::{MouseButton, MouseButtonInput, MouseWheel};
 use bevy::input::ButtonState;
 use bevy::prelude::*;
 
-use std::collections::BTreeMap;
-
 use crate::components::button::*;
 use crate::components::painting::*;
 use crate::components::tile::*;
@@ -37,6 +35,15 @@ pub fn new_map_album(
     if let Some(command) = atomic_command.queue.front() {
         if command == &AtomicCommand::NewMapAlbum {
             map_album.maps.clear();
+            map_album.stairs.clear();
+            map_album.storeys.clear();
+            map_album.elevators.clear();
+            map_album.doors.clear();
+            map_album.exits.clear();
+            map_album.occupants.clear();
+            map_album.compartments.clear();
+            map_album.vents.clear();
+            map_album.shafts.clear();
             let inputs = pipe.input.pop_front().unwrap();
             let input_vec = inputs.split(" ").collect::<Vec<&str>>();
             let name = input_vec[0].to_string();
@@ -101,14 +108,11 @@ pub fn new_paper_album(
     if let Some(command) = atomic_command.queue.front() {
         if command == &AtomicCommand::NewPaperAlbum {
             paper_album.papers.clear();
-            let mut bt_map = BTreeMap::new();
-            for (marker, map) in map_album.maps.iter() {
-                bt_map.insert(marker.clone(), map.clone());
-            }
             let board = query.single();
             let height = 0.95 * board.height;
             let position = board.position;
-            for map_marker in bt_map.keys() {
+            //按楼层从下往上排列页面
+            for map_marker in map_album.ordered_markers().iter() {
                 let map = &map_album.maps[map_marker];
                 let row = map.tiles.len();
                 let col = map.tiles[0].len();
@@ -140,14 +144,11 @@ pub fn new_paper_album_for_show(
     query: Query<&PaperBoard>,
 ) {
     paper_album.papers.clear();
-    let mut bt_map = BTreeMap::new();
-    for (marker, map) in map_album.maps.iter() {
-        bt_map.insert(marker.clone(), map.clone());
-    }
     let board = query.single();
     let height = 0.95 * board.height;
     let position = board.position;
-    for map_marker in bt_map.keys() {
+    //按楼层从下往上排列页面
+    for map_marker in map_album.ordered_markers().iter() {
         let map = &map_album.maps[map_marker];
         let row = map.tiles.len();
         let col = map.tiles[0].len();
@@ -7,6 +7,7 @@ use crate::components::painting::*;
 use crate::components::terminal::*;
 use crate::components::tile::*;
 
+use crate::evacuation::simulation::{SimClock, Simulation};
 use crate::evacuation::things::human::*;
 
 //设置窗口的基本框架
@@ -778,14 +779,19 @@ pub fn update_page_info_box(
     page.page = paper_album.now_paper;
 }
 
-pub fn update_dead_num(mut query: Query<(&mut Text, &DeadBox)>, human: Res<TheCrowd>) {
+pub fn update_dead_num(mut query: Query<(&mut Text, &DeadBox)>, simulation: Res<Simulation>) {
     let (mut text, _) = query.single_mut();
+    let human = &simulation.crowd;
     let dead = human.humans.iter().filter(|&x| x.is_dead == true).count();
     text.sections[0].value = format!("Dead: {}/{}", dead, human.humans.len());
 }
 
-pub fn update_evacuated_num(mut query: Query<(&mut Text, &EvacuatedBox)>, human: Res<TheCrowd>) {
+pub fn update_evacuated_num(
+    mut query: Query<(&mut Text, &EvacuatedBox)>,
+    simulation: Res<Simulation>,
+) {
     let (mut text, _) = query.single_mut();
+    let human = &simulation.crowd;
     let evacuated = human
         .humans
         .iter()
@@ -794,7 +800,17 @@ pub fn update_evacuated_num(mut query: Query<(&mut Text, &EvacuatedBox)>, human:
     text.sections[0].value = format!("Evacuated: {}/{}", evacuated, human.humans.len());
 }
 
-pub fn update_time(mut query: Query<(&mut Text, &TimeBox)>, time: Res<Time>) {
+//显示模拟时间而不是真实时间，暂停时显示||，否则显示倍速
+pub fn update_time(
+    mut query: Query<(&mut Text, &TimeBox)>,
+    simulation: Res<Simulation>,
+    clock: Res<SimClock>,
+) {
     let (mut text, _) = query.single_mut();
-    text.sections[0].value = format!("Time: {:.2}", time.elapsed_seconds());
+    let state = if clock.paused {
+        "||".to_string()
+    } else {
+        format!("x{}", clock.speed())
+    };
+    text.sections[0].value = format!("Time: {:.1} {}", simulation.time, state);
 }
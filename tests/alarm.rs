@@ -0,0 +1,93 @@
+mod common;
+
+use common::{building, marker, parameters};
+use drawmymap2_lib::components::painting::MapAlbum;
+use drawmymap2_lib::components::TileType;
+use drawmymap2_lib::evacuation::things::alarm::{AlarmScope, BuildingAlarm};
+use drawmymap2_lib::evacuation::things::fire::{BuildingFire, Fire};
+use drawmymap2_lib::evacuation::things::smoke::BuildingSmoke;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+//两层，s1的(1,1)是探测器，s0的(1,1)是报警按钮
+fn sensed() -> MapAlbum {
+    let mut album = building(&["s0", "s1"], 5, 8, (2, 6));
+    album.maps.get_mut(&marker("s0")).unwrap().tiles[1][1] = TileType::Alarm;
+    album.maps.get_mut(&marker("s1")).unwrap().tiles[1][1] = TileType::Detector;
+    album
+}
+
+fn fire_and_smoke(album: &MapAlbum) -> (BuildingFire, BuildingSmoke) {
+    let para = parameters();
+    let mut fire = BuildingFire::default();
+    fire.init_from_album(album, &para);
+    let mut smoke = BuildingSmoke::default();
+    smoke.init_from_album(album);
+    (fire, smoke)
+}
+
+//探测器所在格的烟达到阈值时报警，只响本层时另一层不响，过了反应时间才开始疏散
+#[test]
+fn detector_sounds_its_storey() {
+    let album = sensed();
+    let para = parameters();
+    let (fire, mut smoke) = fire_and_smoke(&album);
+    let mut alarm = BuildingAlarm::default();
+    alarm.init_from_album(&album, AlarmScope::Storey);
+    assert!(alarm.check(&fire, &smoke, &para, 1.0).is_empty());
+
+    let s1 = marker("s1");
+    smoke
+        .maps
+        .get_mut(&s1)
+        .unwrap()
+        .add((1, 1), para.detector_threshold);
+    assert_eq!(alarm.check(&fire, &smoke, &para, 2.0), vec![s1.clone()]);
+    assert_eq!(alarm.alarm_time(&s1), Some(2.0));
+    assert_eq!(alarm.alarm_time(&marker("s0")), None);
+    assert!(alarm.check(&fire, &smoke, &para, 3.0).is_empty());
+
+    assert!(!alarm.is_started(&s1, 30.0, 31.0));
+    assert!(alarm.is_started(&s1, 30.0, 32.0));
+    assert!(!alarm.is_started(&marker("s0"), 0.0, 100.0));
+}
+
+//附近着火时报警按钮被按下，整栋楼一起响
+#[test]
+fn call_point_sounds_whole_building() {
+    let album = sensed();
+    let para = parameters();
+    let (mut fire, smoke) = fire_and_smoke(&album);
+    let mut alarm = BuildingAlarm::default();
+    alarm.init_from_album(&album, AlarmScope::Building);
+
+    let storey_fire = fire.maps.get_mut(&marker("s0")).unwrap();
+    let far = (1, 1 + para.call_point_radius + 1);
+    storey_fire.map[far.0][far.1] = Fire::Off;
+    storey_fire.ignite(far, &mut StdRng::seed_from_u64(1));
+    assert!(alarm.check(&fire, &smoke, &para, 1.0).is_empty());
+
+    let storey_fire = fire.maps.get_mut(&marker("s0")).unwrap();
+    let near = (1, 1 + para.call_point_radius);
+    storey_fire.map[near.0][near.1] = Fire::Off;
+    storey_fire.ignite(near, &mut StdRng::seed_from_u64(1));
+    assert_eq!(
+        alarm.check(&fire, &smoke, &para, 2.0),
+        vec![marker("s0"), marker("s1")]
+    );
+    assert_eq!(alarm.first_alarm(), Some(2.0));
+}
+
+//没有探测器和报警按钮时第一次检查就响
+#[test]
+fn no_sensors_sounds_at_start() {
+    let album = building(&["s0", "s1"], 5, 8, (2, 6));
+    let para = parameters();
+    let (fire, smoke) = fire_and_smoke(&album);
+    let mut alarm = BuildingAlarm::default();
+    alarm.init_from_album(&album, AlarmScope::Storey);
+    assert_eq!(
+        alarm.check(&fire, &smoke, &para, 0.1),
+        vec![marker("s0"), marker("s1")]
+    );
+}
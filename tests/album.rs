@@ -0,0 +1,58 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{marker, walled};
+use drawmymap2_lib::components::painting::{MapAlbum, StairEnd, StairLink};
+use drawmymap2_lib::components::TileType;
+
+//旧格式的地图集按名字顺序从下往上排楼层，上下相邻两层同一位置的楼梯格自动相连
+#[test]
+fn legacy_album_pairs_aligned_stairs() {
+    let mut maps = HashMap::new();
+    for name in ["f0", "f1", "f2"] {
+        let mut map = walled(5, 8);
+        map.tiles[2][6] = TileType::Stair;
+        maps.insert(name.to_string(), map);
+    }
+    //只有f1有的楼梯格不连到别的层
+    maps.get_mut("f1").unwrap().tiles[1][1] = TileType::Stair;
+    let album = MapAlbum::from_legacy(maps);
+    assert_eq!(album.stairs.len(), 2);
+    for (lower, upper) in [("f0", "f1"), ("f1", "f2")] {
+        assert!(album.stairs.contains(&StairLink {
+            upper: StairEnd {
+                storey: marker(upper),
+                tile: (2, 6),
+            },
+            lower: StairEnd {
+                storey: marker(lower),
+                tile: (2, 6),
+            },
+        }));
+    }
+    assert!(album.check().is_empty());
+}
+
+#[test]
+fn check_collects_errors_of_every_kind() {
+    let mut album = MapAlbum::new();
+    album.add_map("f0".to_string(), walled(5, 8));
+    album.add_map("f1".to_string(), walled(5, 8));
+    album.fill_storeys();
+    album.stairs.push(StairLink {
+        upper: StairEnd {
+            storey: marker("f1"),
+            tile: (2, 6),
+        },
+        lower: StairEnd {
+            storey: marker("f0"),
+            tile: (2, 6),
+        },
+    });
+    let errors = album.check();
+    assert_eq!(errors.len(), 2);
+    assert!(errors
+        .iter()
+        .all(|error| error.starts_with("stair error: ")));
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::{building, parameters};
+use drawmymap2_lib::evacuation::batch::{run_sweep, sweep_grid, BatchConfig, SweepAxis};
+use serde_json::json;
+
+#[test]
+fn sweep_axis_parses_lists_and_ranges() {
+    let axis = SweepAxis::parse("k=1,2.5,4").unwrap();
+    assert_eq!(axis.field, "k");
+    assert_eq!(json!(axis.values), json!([1, 2.5, 4]));
+
+    //起点和步长都是整数时按整数取值，含终点
+    let axis = SweepAxis::parse("tile_capacity=1:3:1").unwrap();
+    assert_eq!(json!(axis.values), json!([1, 2, 3]));
+    let axis = SweepAxis::parse("p=0.01:0.03:0.01").unwrap();
+    assert_eq!(axis.values.len(), 3);
+    assert!(axis.values.iter().all(|x| x.is_f64()));
+
+    assert!(SweepAxis::parse("seed=1,2").is_err());
+    assert!(SweepAxis::parse("k").is_err());
+    assert!(SweepAxis::parse("k=1:2").is_err());
+    assert!(SweepAxis::parse("k=1:2:0").is_err());
+    assert!(SweepAxis::parse("k=a,b").is_err());
+}
+
+#[test]
+fn sweep_grid_sets_every_combination() {
+    let para = parameters();
+    let axes = [
+        SweepAxis::parse("tile_capacity=1:2:1").unwrap(),
+        SweepAxis::parse("k=3,6,9").unwrap(),
+    ];
+    let grid = sweep_grid(&para, &axes).unwrap();
+    assert_eq!(grid.len(), 6);
+    assert_eq!(grid[5].1.tile_capacity, 2);
+    assert_eq!(grid[5].1.k, 9.0);
+
+    let bad = [SweepAxis::parse("tile_capacity=0.5,1").unwrap()];
+    assert!(sweep_grid(&para, &bad).is_err());
+    let unknown = [SweepAxis::parse("no_such_field=1").unwrap()];
+    assert!(sweep_grid(&para, &unknown).is_err());
+}
+
+//血量和速度的离散程度取0时每个人都一样，不会因为取值范围为空而出错
+#[test]
+fn sweep_runs_with_zero_spread() {
+    let album = building(&["s0"], 6, 8, (1, 6));
+    let para = parameters();
+    let axes = [
+        SweepAxis::parse("h=0:0.2:0.1").unwrap(),
+        SweepAxis::parse("v=0,0.2").unwrap(),
+    ];
+    let config = BatchConfig {
+        runs: 2,
+        max_time: 60.0,
+        ..Default::default()
+    };
+    let rows = run_sweep(&album, &para, &axes, &config).unwrap();
+    assert_eq!(rows.len(), 6);
+}
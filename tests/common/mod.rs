@@ -0,0 +1,56 @@
+#![allow(dead_code)]
+
+use drawmymap2_lib::components::painting::{Map, MapAlbum, MapMarker, StairEnd, StairLink};
+use drawmymap2_lib::components::TileType;
+use drawmymap2_lib::evacuation::things::NeededParameters;
+
+pub fn marker(name: &str) -> MapMarker {
+    MapMarker {
+        name: name.to_string(),
+    }
+}
+
+//四周是墙的一层
+pub fn walled(rows: usize, cols: usize) -> Map {
+    let mut map = Map::new(rows, cols);
+    for i in 0..rows {
+        for j in 0..cols {
+            if i == 0 || j == 0 || i == rows - 1 || j == cols - 1 {
+                map.tiles[i][j] = TileType::Wall;
+            }
+        }
+    }
+    map
+}
+
+//names从下往上的几层，每层rows×cols四周是墙，stair位置上是楼梯格，上下层的楼梯格两两相连，
+//首层左边墙的中间开一个出口
+pub fn building(names: &[&str], rows: usize, cols: usize, stair: (usize, usize)) -> MapAlbum {
+    let mut album = MapAlbum::new();
+    for name in names {
+        let mut map = walled(rows, cols);
+        map.tiles[stair.0][stair.1] = TileType::Stair;
+        album.add_map(name.to_string(), map);
+    }
+    album.maps.get_mut(&marker(names[0])).unwrap().tiles[rows / 2][0] = TileType::Exit;
+    for pair in names.windows(2) {
+        album.stairs.push(StairLink {
+            upper: StairEnd {
+                storey: marker(pair[1]),
+                tile: stair,
+            },
+            lower: StairEnd {
+                storey: marker(pair[0]),
+                tile: stair,
+            },
+        });
+    }
+    album.fill_storeys();
+    album
+}
+
+pub fn parameters() -> NeededParameters {
+    let mut para = NeededParameters::load("needed_parameters");
+    para.seed = Some(7);
+    para
+}
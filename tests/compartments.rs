@@ -0,0 +1,68 @@
+mod common;
+
+use common::{building, marker, parameters};
+use drawmymap2_lib::components::painting::{CompartmentSpec, MapAlbum};
+use drawmymap2_lib::components::TileType;
+use drawmymap2_lib::evacuation::simulation::Simulation;
+use drawmymap2_lib::evacuation::things::compartment::HorizontalMode;
+use drawmymap2_lib::evacuation::things::fire::Fire;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+//一层被第6列的墙分成左右两个防烟分区，墙上(3,6)开门，左边分区的避难区是(3,2)，
+//右边分区没有指定避难格，整个分区的地板都是避难区；左边分区的(1,1)是可燃物
+fn two_compartments() -> MapAlbum {
+    let mut album = building(&["s0"], 7, 13, (5, 11));
+    let s0 = marker("s0");
+    let map = album.maps.get_mut(&s0).unwrap();
+    for i in 1..6 {
+        map.tiles[i][6] = TileType::Wall;
+    }
+    map.tiles[3][6] = TileType::Door;
+    map.tiles[1][1] = TileType::Furniture;
+    for (name, area, refuges) in [
+        ("west", ((1, 1), (5, 5)), vec![(3, 2)]),
+        ("east", ((1, 7), (5, 11)), vec![]),
+    ] {
+        album.compartments.push(CompartmentSpec {
+            storey: s0.clone(),
+            name: name.to_string(),
+            area,
+            refuges,
+        });
+    }
+    album
+}
+
+//左边分区着火后不再安全，所有人都躲进右边分区的避难区，就地避难时模拟结束
+#[test]
+fn occupants_shelter_in_the_safe_compartment() {
+    let album = two_compartments();
+    let mut para = parameters();
+    para.p = 0.0;
+    para.human_seed = 0.3;
+    para.pre_movement = 0.0;
+    para.horizontal_evacuation = HorizontalMode::Refuge;
+    para.s = 0.01;
+    let mut simulation = Simulation::new(&album, &para);
+    let s0 = marker("s0");
+    let refuge = &simulation.pass.maps[&s0].refuge;
+    assert!(refuge.contains(&(3, 2)) && refuge.contains(&(3, 10)));
+    assert!(simulation.crowd.humans.len() > 1);
+
+    let storey_fire = simulation.fire.maps.get_mut(&s0).unwrap();
+    assert_eq!(storey_fire.map[1][1], Fire::Off);
+    storey_fire.ignite((1, 1), &mut StdRng::seed_from_u64(1));
+    simulation.step(0.1);
+    let refuge = &simulation.pass.maps[&s0].refuge;
+    assert!(!refuge.is_empty() && refuge.iter().all(|&(_, j)| j > 6));
+
+    simulation.run_to_end(0.1, 300.0);
+    assert!(simulation.is_finished());
+    let result = simulation.result();
+    assert_eq!(result.dead, 0);
+    assert_eq!(result.in_refuge, result.population);
+    assert!(result.compartments[0].unsafe_at.is_some());
+    assert_eq!(result.compartments[1].unsafe_at, None);
+    assert_eq!(result.compartments[1].sheltering, result.in_refuge);
+}
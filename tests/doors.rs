@@ -0,0 +1,66 @@
+mod common;
+
+use common::{building, marker, parameters};
+use drawmymap2_lib::components::painting::{DoorMode, DoorSpec, MapAlbum};
+use drawmymap2_lib::components::TileType;
+use drawmymap2_lib::evacuation::things::door::BuildingDoors;
+use drawmymap2_lib::evacuation::things::fire::BuildingFire;
+use drawmymap2_lib::evacuation::things::smoke::{BuildingSmoke, Smoke};
+
+//一层中间有一道墙，墙上开一扇门
+fn two_rooms(mode: DoorMode) -> MapAlbum {
+    let mut album = building(&["s0"], 7, 13, (1, 11));
+    let map = album.maps.get_mut(&marker("s0")).unwrap();
+    for i in 1..6 {
+        map.tiles[i][6] = TileType::Wall;
+    }
+    map.tiles[3][6] = TileType::Door;
+    album.doors.push(DoorSpec {
+        storey: marker("s0"),
+        tile: (3, 6),
+        mode,
+        fire_door: false,
+        flow: None,
+    });
+    album
+}
+
+//自闭门在这一步关上时，同一步里就开始挡火挡烟
+#[test]
+fn door_closing_this_step_blocks_at_once() {
+    let album = two_rooms(DoorMode::SelfClosing);
+    let para = parameters();
+    let mut fire = BuildingFire::default();
+    fire.init_from_album(&album, &para);
+    let mut smoke = BuildingSmoke::default();
+    smoke.init_from_album(&album);
+    let mut doors = BuildingDoors::default();
+    doors.init_from_album(&album, &para, &mut fire, &mut smoke);
+    let s0 = marker("s0");
+    assert!(fire.maps[&s0].barrier[3][6]);
+
+    let door = doors
+        .maps
+        .get_mut(&s0)
+        .unwrap()
+        .doors
+        .get_mut(&(3, 6))
+        .unwrap();
+    door.open = true;
+    door.closing = 1.0;
+    doors.tick(0.1, &mut fire, &mut smoke);
+    assert!(!fire.maps[&s0].barrier[3][6]);
+    assert!(smoke.maps[&s0].map[3][6].is_open());
+
+    let door = doors
+        .maps
+        .get_mut(&s0)
+        .unwrap()
+        .doors
+        .get_mut(&(3, 6))
+        .unwrap();
+    door.closing = 0.05;
+    doors.tick(0.1, &mut fire, &mut smoke);
+    assert!(fire.maps[&s0].barrier[3][6]);
+    assert!(matches!(smoke.maps[&s0].map[3][6], Smoke::Closed(_)));
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::{building, marker, parameters};
+use drawmymap2_lib::components::painting::{ElevatorShaft, MapAlbum, StairEnd};
+use drawmymap2_lib::components::TileType;
+use drawmymap2_lib::evacuation::simulation::Simulation;
+use drawmymap2_lib::evacuation::things::elevator::BuildingElevators;
+use drawmymap2_lib::evacuation::things::fire::{BuildingFire, Fire};
+use drawmymap2_lib::evacuation::things::human::Human;
+use drawmymap2_lib::evacuation::things::smoke::BuildingSmoke;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn stop(storey: &str) -> StairEnd {
+    StairEnd {
+        storey: marker(storey),
+        tile: (1, 1),
+    }
+}
+
+//两层，每层(1,1)是同一部电梯的停靠格
+fn lift() -> MapAlbum {
+    let mut album = building(&["s0", "s1"], 5, 8, (2, 6));
+    for map in album.maps.values_mut() {
+        map.tiles[1][1] = TileType::Elevator;
+    }
+    album.elevators.push(ElevatorShaft {
+        name: "lift".to_string(),
+        stops: vec![stop("s0"), stop("s1")],
+    });
+    album
+}
+
+//在二楼停靠格等电梯的人
+fn waiting() -> Vec<Human> {
+    vec![Human {
+        id: 0,
+        storey: marker("s1"),
+        now_tile: (1, 1),
+        elevator: Some(0),
+        ..Default::default()
+    }]
+}
+
+//电梯被叫到二楼接人，送到首层
+#[test]
+fn elevator_carries_caller_down() {
+    let album = lift();
+    let para = parameters();
+    let mut elevators = BuildingElevators::default();
+    elevators.init_from_album(&album);
+    assert_eq!(elevators.elevators[0].floor, 0);
+    let mut humans = waiting();
+    elevators.elevators[0].call(1);
+    let mut arrived = Vec::new();
+    for _ in 0..200 {
+        arrived = elevators.run(0.5, &para, &mut humans);
+        if !arrived.is_empty() {
+            break;
+        }
+    }
+    assert_eq!(arrived, vec![(0, stop("s0"))]);
+    assert_eq!(elevators.elevators[0].served, 1);
+    assert_eq!(elevators.elevators[0].trips, 2);
+}
+
+//停靠格旁着火后电梯锁定，不再响应呼叫，等候的人也不会被接走
+#[test]
+fn locked_elevator_stops_serving() {
+    let album = lift();
+    let para = parameters();
+    let mut fire = BuildingFire::default();
+    fire.init_from_album(&album, &para);
+    let mut smoke = BuildingSmoke::default();
+    smoke.init_from_album(&album);
+    let mut elevators = BuildingElevators::default();
+    elevators.init_from_album(&album);
+    let mut humans = waiting();
+    elevators.elevators[0].call(1);
+    assert!(elevators
+        .lockout(&fire, &smoke, para.elevator_smoke_limit, 1.0)
+        .is_empty());
+
+    let storey_fire = fire.maps.get_mut(&marker("s1")).unwrap();
+    storey_fire.map[1][2] = Fire::Off;
+    storey_fire.ignite((1, 2), &mut StdRng::seed_from_u64(1));
+    assert_eq!(
+        elevators.lockout(&fire, &smoke, para.elevator_smoke_limit, 2.0),
+        vec![0]
+    );
+    assert!(elevators.elevators[0].calls.is_empty());
+    assert!(elevators
+        .lockout(&fire, &smoke, para.elevator_smoke_limit, 3.0)
+        .is_empty());
+
+    elevators.elevators[0].call(1);
+    for _ in 0..200 {
+        assert!(elevators.run(0.5, &para, &mut humans).is_empty());
+    }
+    assert!(!humans[0].riding);
+    assert_eq!(elevators.elevators[0].floor, 0);
+    assert_eq!(elevators.elevators[0].served, 0);
+    assert_eq!(elevators.elevators[0].usage().locked_at, Some(2.0));
+}
+
+//模拟中电梯锁定后各层的通行图里不再有它的停靠格
+#[test]
+fn locked_stops_leave_the_routes() {
+    let album = lift();
+    let mut para = parameters();
+    para.p = 0.0;
+    para.human_seed = 0.0;
+    let mut simulation = Simulation::new(&album, &para);
+    let s1 = marker("s1");
+    assert_eq!(simulation.pass.maps[&s1].elevator, vec![(1, 1)]);
+    let storey_fire = simulation.fire.maps.get_mut(&s1).unwrap();
+    storey_fire.map[1][2] = Fire::Off;
+    storey_fire.ignite((1, 2), &mut StdRng::seed_from_u64(1));
+    simulation.step(0.1);
+    assert!(simulation.elevators.elevators[0].is_locked());
+    assert!(simulation.pass.maps[&s1].elevator.is_empty());
+    assert!(simulation.evacuation.maps[&s1]
+        .iter()
+        .all(|group| !group.lift));
+}
@@ -0,0 +1,142 @@
+use std::time::{Duration, Instant};
+
+use drawmymap2_lib::components::painting::MapMarker;
+use drawmymap2_lib::evacuation::things::fire::{BuildingFire, Fire, StoreyFire};
+use drawmymap2_lib::evacuation::things::smoke::{BuildingSmoke, StoreySmoke};
+use drawmymap2_lib::evacuation::things::NeededParameters;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+//300×300的一层全是可燃物，随机点燃300格并先烧30秒，再计时100步spread的平均耗时；
+//引燃时间设为无穷大让燃烧格一直是这几百个，k取默认参数和k=1（影响半径大得多）两种情况
+//cargo test --release --test fire_spread -- --ignored --nocapture
+#[test]
+#[ignore]
+fn spread_stays_fast_on_large_storey() {
+    let n = 300;
+    let para = NeededParameters::load("needed_parameters");
+    for k in [para.k, 1.0] {
+        let marker = MapMarker {
+            name: "large".to_string(),
+        };
+        let mut storey_fire = StoreyFire::new_with(n, n, para.burning_time, para.smoking_time, k);
+        storey_fire.ignition_delay = f32::INFINITY;
+        for row in storey_fire.map.iter_mut() {
+            row.fill(Fire::Off);
+        }
+        let mut rng = StdRng::seed_from_u64(1);
+        while storey_fire.burning.len() < 300 {
+            let tile = (rng.gen_range(0..n), rng.gen_range(0..n));
+            storey_fire.ignite(tile, &mut rng);
+        }
+        let mut fire = BuildingFire::default();
+        fire.maps.insert(marker.clone(), storey_fire);
+        let mut smoke = BuildingSmoke::default();
+        smoke.maps.insert(marker.clone(), StoreySmoke::new(n, n));
+        let dt = Duration::from_millis(100);
+        for _ in 0..300 {
+            fire.burning(&mut smoke, para.s, dt);
+        }
+
+        let steps = 100;
+        let mut spent = Duration::ZERO;
+        for _ in 0..steps {
+            fire.burning(&mut smoke, para.s, dt);
+            let start = Instant::now();
+            fire.maps.get_mut(&marker).unwrap().spread(0.1, &mut rng);
+            spent += start.elapsed();
+        }
+        let storey_fire = &fire.maps[&marker];
+        let per_step = spent / steps;
+        println!(
+            "k {}: {} burning cells, reach {}, {:?} per step",
+            k,
+            storey_fire.burning.len(),
+            storey_fire.reach(),
+            per_step
+        );
+        assert!(storey_fire.burning.len() >= 100);
+        assert!(
+            per_step.as_millis() < 50,
+            "spread took {:?} per step",
+            per_step
+        );
+    }
+}
+
+//全是可燃物的一层，引燃后立即着火
+fn furnished(rows: usize, cols: usize, k: f32) -> StoreyFire {
+    let mut storey_fire = StoreyFire::new_with(rows, cols, 100.0, 1.0, k);
+    storey_fire.ignition_delay = 0.0;
+    for row in storey_fire.map.iter_mut() {
+        row.fill(Fire::Off);
+    }
+    storey_fire
+}
+
+//点燃一格并让它已经烧了seconds秒
+fn burn(storey_fire: &mut StoreyFire, tile: (usize, usize), seconds: f32, rng: &mut StdRng) {
+    storey_fire.ignite(tile, rng);
+    if let Fire::On(timer) = &mut storey_fire.map[tile.0][tile.1] {
+        timer.burning_timer.tick(Duration::from_secs_f32(seconds));
+    }
+}
+
+//烧了5秒的格能引燃距离小于5格的可燃物，但隔着一整列墙的格不会被引燃，挡住火的墙格记为被波及
+#[test]
+fn walls_block_spread() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut open = furnished(7, 9, 1.0);
+    burn(&mut open, (3, 1), 5.0, &mut rng);
+    let mut walled = open.clone();
+    for i in 0..7 {
+        walled.barrier[i][4] = true;
+        walled.map[i][4] = Fire::NeverBurn;
+    }
+
+    open.spread(0.1, &mut rng);
+    assert!(open.burning.contains(&(3, 3)));
+    assert!(open.burning.contains(&(3, 5)));
+    assert!(!open.burning.contains(&(3, 6)));
+    assert!(open.heated.is_empty());
+
+    walled.spread(0.1, &mut rng);
+    assert!(walled.burning.contains(&(3, 3)));
+    assert!(walled.burning.contains(&(0, 3)));
+    assert!((0..7).all(|i| (5..9).all(|j| !walled.burning.contains(&(i, j)))));
+    assert!(walled.heated.contains(&(3, 4)));
+}
+
+//没有挡火格时按行记圆的快速算法与逐格检查t/d>k的结果一致
+#[test]
+fn spread_matches_distance_rule() {
+    let (n, k) = (40, 2.0);
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut storey_fire = furnished(n, n, k);
+    while storey_fire.burning.len() < 6 {
+        let tile = (rng.gen_range(0..n), rng.gen_range(0..n));
+        let seconds = rng.gen_range(1.0..20.0);
+        burn(&mut storey_fire, tile, seconds, &mut rng);
+    }
+    let sources: Vec<((usize, usize), f32)> = storey_fire
+        .burning
+        .iter()
+        .map(|&(i, j)| ((i, j), storey_fire.map[i][j].get_burning_time()))
+        .collect();
+    storey_fire.spread(0.1, &mut rng);
+    for i in 0..n {
+        for j in 0..n {
+            let expected = sources.iter().any(|&((x, y), time)| {
+                let distance = ((i.abs_diff(x).pow(2) + j.abs_diff(y).pow(2)) as f32).sqrt();
+                time / distance > k
+            });
+            assert_eq!(
+                storey_fire.burning.contains(&(i, j)),
+                expected,
+                "({}, {})",
+                i,
+                j
+            );
+        }
+    }
+}
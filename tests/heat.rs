@@ -0,0 +1,81 @@
+mod common;
+
+use common::parameters;
+use drawmymap2_lib::evacuation::things::fire::{Fire, StoreyFire};
+use drawmymap2_lib::evacuation::things::heat::{heat_dose_rate, StoreyHeat};
+use drawmymap2_lib::evacuation::things::human::Human;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+//(3,2)着火，第4列是一整列墙
+fn fire_by_wall() -> StoreyFire {
+    let mut storey_fire = StoreyFire::new(7, 9);
+    for i in 0..7 {
+        storey_fire.barrier[i][4] = true;
+    }
+    storey_fire.map[3][2] = Fire::Off;
+    storey_fire.ignite((3, 2), &mut StdRng::seed_from_u64(1));
+    storey_fire
+}
+
+//燃烧格附近升温，隔着墙的格即使在传热范围内也保持环境温度
+#[test]
+fn walls_keep_heat_out() {
+    let mut para = parameters();
+    para.heat_time_constant = 0.0;
+    let storey_fire = fire_by_wall();
+    let mut heat = StoreyHeat::new(7, 9, para.ambient_temperature);
+    heat.update(&storey_fire, &para, 0.1);
+    assert_eq!(heat.temperature[3][2], para.flame_temperature);
+    assert!(heat.rise((3, 3), &para) > 0.0);
+    assert!(heat.radiation[3][3] > 0.0);
+    assert_eq!(heat.rise((3, 5), &para), 0.0);
+    assert_eq!(heat.radiation[3][5], 0.0);
+
+    let mut open = storey_fire.clone();
+    for i in 0..7 {
+        open.barrier[i][4] = false;
+    }
+    heat.update(&open, &para, 0.1);
+    assert!(heat.rise((3, 5), &para) > 0.0);
+}
+
+//气温按时间常数逐渐趋向目标温度
+#[test]
+fn temperature_relaxes_toward_target() {
+    let mut para = parameters();
+    para.heat_time_constant = 10.0;
+    let storey_fire = fire_by_wall();
+    let mut heat = StoreyHeat::new(7, 9, para.ambient_temperature);
+    heat.update(&storey_fire, &para, 10.0);
+    let rise = para.flame_temperature - para.ambient_temperature;
+    let expected = para.ambient_temperature + rise * (1.0 - (-1.0f32).exp());
+    assert!((heat.temperature[3][2] - expected).abs() < 1e-3);
+}
+
+//站在火旁边的人累计热剂量并掉血，环境温度下剂量为0
+#[test]
+fn heat_dose_hurts_near_fire() {
+    let mut para = parameters();
+    para.heat_time_constant = 0.0;
+    assert_eq!(heat_dose_rate(para.ambient_temperature, 0.0, &para), 0.0);
+    let mut heat = StoreyHeat::new(7, 9, para.ambient_temperature);
+    heat.update(&fire_by_wall(), &para, 0.1);
+
+    let mut near = Human {
+        now_tile: (3, 3),
+        ..Default::default()
+    };
+    let mut behind = Human {
+        now_tile: (3, 5),
+        ..Default::default()
+    };
+    for _ in 0..10 {
+        near.heat_damage(&heat, &para, 1.0);
+        behind.heat_damage(&heat, &para, 1.0);
+    }
+    assert!(near.heat_dose > 0.0);
+    assert!((near.hp - (near.max_hp - near.heat_dose * near.max_hp)).abs() < 1e-3);
+    assert_eq!(behind.heat_dose, 0.0);
+    assert_eq!(behind.hp, behind.max_hp);
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use common::{building, marker, parameters};
+use drawmymap2_lib::components::painting::OccupantMix;
+use drawmymap2_lib::evacuation::simulation::Simulation;
+use drawmymap2_lib::evacuation::things::behaviour::Behaviour;
+use drawmymap2_lib::evacuation::things::rescue::Role;
+
+fn mix(storey: &str, room: Option<((usize, usize), (usize, usize))>, name: &str) -> OccupantMix {
+    OccupantMix {
+        storey: marker(storey),
+        room,
+        mix: BTreeMap::from([(name.to_string(), 1.0)]),
+    }
+}
+
+//配置了人员类型时按比例抽员工和卧床病人的设置不起作用，角色和状态都由人员类型决定
+#[test]
+fn profiles_replace_ratio_roles() {
+    let mut album = building(&["s0", "s1"], 5, 8, (2, 6));
+    album.occupants.push(mix("s0", None, "ambulatory"));
+    album.occupants.push(mix("s1", None, "ambulatory"));
+    let mut para = parameters();
+    para.p = 0.0;
+    para.human_seed = 1.0;
+    para.staff_ratio = 1.0;
+    para.bedridden_ratio = 1.0;
+    para.profiles = "occupant_profiles".to_string();
+    let simulation = Simulation::new(&album, &para);
+    assert!(!simulation.crowd.humans.is_empty());
+    assert!(simulation.crowd.humans.iter().all(|human| {
+        human.profile == "ambulatory"
+            && human.role == Role::Occupant
+            && human.behaviour == Behaviour::Normal
+    }));
+}
+
+//二楼没有电梯，坐轮椅的人走不了楼梯，由同层的员工背下楼疏散
+#[test]
+fn wheelchair_user_upstairs_is_carried_down() {
+    let mut album = building(&["s0", "s1"], 5, 8, (2, 6));
+    album.occupants.push(mix("s0", None, "ambulatory"));
+    album
+        .occupants
+        .push(mix("s1", Some(((1, 1), (1, 1))), "wheelchair"));
+    album.occupants.push(mix("s1", None, "staff"));
+    let mut para = parameters();
+    para.p = 0.0;
+    para.human_seed = 1.0;
+    para.profiles = "occupant_profiles".to_string();
+    let mut simulation = Simulation::new(&album, &para);
+    let wheelchair = simulation
+        .crowd
+        .humans
+        .iter()
+        .find(|human| human.profile == "wheelchair")
+        .unwrap()
+        .id;
+    assert_eq!(simulation.crowd.humans[wheelchair].storey, marker("s1"));
+    assert!(!simulation.crowd.humans[wheelchair].use_stairs);
+
+    simulation.run_to_end(0.1, 600.0);
+    assert!(simulation.is_finished());
+    assert!(simulation.crowd.humans[wheelchair].is_evacuated);
+    let result = simulation.result();
+    assert_eq!(result.evacuated, result.population);
+    assert!(result
+        .rescues
+        .iter()
+        .any(|record| record.patient == wheelchair && record.delivered_at.is_some()));
+}
+
+//每个人的自由步行速度在max_speed*(1±v)内随机，max_speed本身不变；人员类型的速度同样只改自由步行速度
+#[test]
+fn speed_draw_keeps_max_speed() {
+    let mut album = building(&["s0", "s1"], 5, 8, (2, 6));
+    let mut para = parameters();
+    para.p = 0.0;
+    para.human_seed = 1.0;
+    para.v = 0.2;
+    let simulation = Simulation::new(&album, &para);
+    let humans = &simulation.crowd.humans;
+    assert!(humans.iter().all(|human| {
+        human.max_speed == 1.5
+            && human.free_speed >= 1.5 * 0.8
+            && human.free_speed <= 1.5 * 1.2
+            && human.speed == human.free_speed
+    }));
+    assert!(humans
+        .iter()
+        .any(|human| human.free_speed != humans[0].free_speed));
+
+    album.occupants.push(mix("s0", None, "staff"));
+    album.occupants.push(mix("s1", None, "staff"));
+    para.profiles = "occupant_profiles".to_string();
+    let simulation = Simulation::new(&album, &para);
+    assert!(simulation.crowd.humans.iter().all(|human| {
+        human.max_speed == 1.4 && (human.free_speed - 1.4).abs() <= 1.4 * 0.1 + 1e-6
+    }));
+}
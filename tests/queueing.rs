@@ -0,0 +1,74 @@
+mod common;
+
+use common::{building, marker, parameters};
+use drawmymap2_lib::components::painting::{DoorMode, DoorSpec, MapAlbum};
+use drawmymap2_lib::components::TileType;
+use drawmymap2_lib::evacuation::simulation::Simulation;
+use drawmymap2_lib::evacuation::things::occupancy::FlowKind;
+
+//一层中间有一道墙，墙上(3,6)开一扇通行能力为0.5人/秒的门，出口在左边
+fn narrow_door() -> MapAlbum {
+    let mut album = building(&["s0"], 7, 13, (1, 11));
+    let map = album.maps.get_mut(&marker("s0")).unwrap();
+    for i in 1..6 {
+        map.tiles[i][6] = TileType::Wall;
+    }
+    map.tiles[3][6] = TileType::Door;
+    album.doors.push(DoorSpec {
+        storey: marker("s0"),
+        tile: (3, 6),
+        mode: DoorMode::Open,
+        fire_door: false,
+        flow: Some(0.5),
+    });
+    album
+}
+
+//每一格最多站tile_capacity个人，挤满时后面的人排队，最后所有人都走出去
+#[test]
+fn tiles_never_hold_more_than_capacity() {
+    let album = narrow_door();
+    let mut para = parameters();
+    para.p = 0.0;
+    para.human_seed = 1.0;
+    para.tile_capacity = 1;
+    let mut simulation = Simulation::new(&album, &para);
+    while !simulation.is_finished() && simulation.time < 600.0 {
+        simulation.step(0.1);
+        let most = simulation.occupancy.maps[&marker("s0")]
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap();
+        assert!(
+            most <= 1,
+            "{} people on one tile at {}",
+            most,
+            simulation.time
+        );
+    }
+    assert!(simulation.is_finished());
+    assert_eq!(simulation.dead_num(), 0);
+}
+
+//门的实测流量不超过它的通行能力
+#[test]
+fn door_flow_caps_the_passing_rate() {
+    let album = narrow_door();
+    let mut para = parameters();
+    para.p = 0.0;
+    para.human_seed = 1.0;
+    let mut simulation = Simulation::new(&album, &para);
+    simulation.run_to_end(0.1, 600.0);
+    assert!(simulation.is_finished());
+    let result = simulation.result();
+    let door = result
+        .flows
+        .iter()
+        .find(|flow| flow.kind == FlowKind::Door && flow.tile == (3, 6))
+        .unwrap();
+    assert!(door.passed > 10);
+    assert!(door.rate() <= 0.5 + 1e-3, "door rate {}", door.rate());
+    assert!(door.rate() >= 0.4, "door rate {}", door.rate());
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::{building, parameters};
+use drawmymap2_lib::evacuation::simulation::Simulation;
+use drawmymap2_lib::evacuation::things::behaviour::Behaviour;
+use drawmymap2_lib::evacuation::things::rescue::Role;
+
+//两层楼里一半是员工，其余都是卧床病人，每个病人要两名员工一起背（同层没有空闲的员工时到了的人先背），
+//全部病人都由员工背出楼外
+#[test]
+fn staff_carry_every_patient_out() {
+    let album = building(&["s0", "s1"], 6, 10, (2, 8));
+    let mut para = parameters();
+    para.p = 0.0;
+    para.human_seed = 0.3;
+    para.staff_ratio = 0.5;
+    para.bedridden_ratio = 1.0;
+    para.staff_per_patient = 2;
+    let mut simulation = Simulation::new(&album, &para);
+    let humans = &simulation.crowd.humans;
+    let patients: Vec<usize> = humans
+        .iter()
+        .filter(|x| x.behaviour == Behaviour::Incapacitated)
+        .map(|x| x.id)
+        .collect();
+    let staff = humans.iter().filter(|x| x.role == Role::Staff).count();
+    assert!(!patients.is_empty() && staff >= 2);
+    assert!(patients
+        .iter()
+        .any(|&id| simulation.crowd.humans[id].storey.name == "s1"));
+
+    simulation.run_to_end(0.1, 1800.0);
+    assert!(simulation.is_finished());
+    let result = simulation.result();
+    assert_eq!(result.dead, 0);
+    assert_eq!(result.evacuated, result.population);
+    assert_eq!(result.rescued(), patients.len());
+    assert!(result.rescues.iter().any(|record| record.staff.len() >= 2));
+    for record in result.rescues.iter() {
+        assert!(patients.contains(&record.patient));
+        assert!(!record.staff.is_empty());
+        let picked_up = record.picked_up_at.unwrap();
+        assert!(record.assigned_at <= picked_up && picked_up <= record.delivered_at.unwrap());
+    }
+}
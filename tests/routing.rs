@@ -0,0 +1,113 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{marker, parameters};
+use drawmymap2_lib::evacuation::things::behaviour::Behaviour;
+use drawmymap2_lib::evacuation::things::human::{
+    BuildingEvacuation, BuildingPass, Familiarity, Human, Pass, StoreyPass,
+};
+use drawmymap2_lib::evacuation::things::smoke::StoreySmoke;
+
+//电梯停靠格被锁定后目标组重建，排在后面的避难区的位置前移，排队人数仍跟着避难区
+#[test]
+fn queues_follow_groups_after_rebuild() {
+    let storey = marker("s1");
+    let mut pass = StoreyPass::new(5, 9);
+    pass.exit = vec![(2, 0)];
+    pass.elevator = vec![(0, 4), (4, 4)];
+    pass.refuge = vec![(2, 8)];
+    let mut building_pass = BuildingPass::default();
+    building_pass.maps.insert(storey.clone(), pass);
+    let mut evacuation = BuildingEvacuation::default();
+    evacuation.update_from_pass(&building_pass, 0.0);
+    assert_eq!(evacuation.maps[&storey].len(), 4);
+
+    let humans: Vec<Human> = (0..3)
+        .map(|id| Human {
+            id,
+            storey: storey.clone(),
+            now_tile: (id + 1, 7),
+            target_tile: (2, 8),
+            ..Default::default()
+        })
+        .collect();
+    evacuation.update_queues(&humans, 3);
+    assert_eq!(evacuation.queues[&storey][&(2, 8)], 3);
+
+    building_pass
+        .maps
+        .get_mut(&storey)
+        .unwrap()
+        .elevator
+        .retain(|&tile| tile != (0, 4));
+    evacuation.update_from_pass(&building_pass, 0.0);
+    let groups = &evacuation.maps[&storey];
+    assert_eq!(groups.len(), 3);
+    assert!(groups[2].refuge);
+    assert_eq!(evacuation.queues[&storey].get(&(2, 8)), Some(&3));
+    assert_eq!(evacuation.queues[&storey].get(&(0, 4)), None);
+}
+
+//关着的门里留着的烟同样计入通行的危险值
+#[test]
+fn closed_door_smoke_counts_as_danger() {
+    let mut pass = StoreyPass::new(3, 3);
+    let mut smoke = StoreySmoke::new(3, 3);
+    smoke.add((1, 1), 2.0);
+    smoke.map[1][1].set_closed(true);
+    pass.update_from_smoke(&smoke);
+    match &pass.map[1][1] {
+        Pass::Passable(danger) => assert_eq!(danger.value, 2.0),
+        Pass::Impassable => panic!("door cell should stay passable"),
+    }
+}
+
+//(2,3)的人离左边出口3格、离右边的主出口5格：平时走近的出口，左边出口前排队的人多了改走右边，
+//访客只认主出口，惊慌的人不看排队
+#[test]
+fn exit_choice_weighs_distance_queues_and_familiarity() {
+    let storey = marker("s0");
+    let mut pass = StoreyPass::new(5, 9);
+    pass.exit = vec![(2, 0), (2, 8)];
+    pass.main_exit = vec![(2, 8)];
+    let mut building_pass = BuildingPass::default();
+    building_pass.maps.insert(storey.clone(), pass);
+    let mut evacuation = BuildingEvacuation::default();
+    evacuation.update_from_pass(&building_pass, 0.0);
+    let groups = &evacuation.maps[&storey];
+    let west = groups.iter().position(|g| g.key() == (2, 0)).unwrap();
+    let east = groups.iter().position(|g| g.key() == (2, 8)).unwrap();
+
+    let mut para = parameters();
+    para.queue_weight = 1.0;
+    let start = (2, 3);
+    let mut human = Human {
+        storey: storey.clone(),
+        now_tile: start,
+        ..Default::default()
+    };
+    let empty = HashMap::new();
+    let crowded = HashMap::from([((2, 0), 3)]);
+    assert_eq!(
+        human.choose_target(groups, &empty, &para, start),
+        Some(west)
+    );
+    assert_eq!(
+        human.choose_target(groups, &crowded, &para, start),
+        Some(east)
+    );
+
+    human.behaviour = Behaviour::Panicked;
+    assert_eq!(
+        human.choose_target(groups, &crowded, &para, start),
+        Some(west)
+    );
+
+    human.behaviour = Behaviour::Normal;
+    human.familiarity = Familiarity::MainOnly;
+    assert_eq!(
+        human.choose_target(groups, &empty, &para, start),
+        Some(east)
+    );
+}
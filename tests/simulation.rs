@@ -0,0 +1,124 @@
+mod common;
+
+use common::{building, parameters};
+use drawmymap2_lib::components::painting::MapAlbum;
+use drawmymap2_lib::components::TileType;
+use drawmymap2_lib::evacuation::simulation::{SimClock, SimEvent, Simulation};
+
+//三层的小楼，楼梯在每层的同一位置，每层中间一行放可燃物
+fn tower() -> MapAlbum {
+    let mut album = building(&["s0", "s1", "s2"], 7, 12, (3, 10));
+    for map in album.maps.values_mut() {
+        for j in 2..6 {
+            map.tiles[3][j] = TileType::Furniture;
+        }
+    }
+    album
+}
+
+#[test]
+fn same_seed_gives_same_result() {
+    let album = tower();
+    let mut para = parameters();
+    para.p = 0.05;
+    let run = || {
+        let mut simulation = Simulation::new(&album, &para);
+        simulation.run_to_end(0.1, 120.0);
+        simulation.result()
+    };
+    let first = run();
+    //有人被烧死也有人逃出，火势、烟雾和人员移动都参与了比较
+    assert!(first.dead > 0 && first.evacuated > 0);
+    assert_eq!(first, run());
+}
+
+#[test]
+fn clock_follows_speed_and_pause() {
+    let mut clock = SimClock::new(0.1);
+    assert_eq!(clock.speed(), 1.0);
+    assert_eq!(clock.advance(0.35), 3);
+    clock.speed_up();
+    assert_eq!(clock.speed(), 2.0);
+    //上一帧剩下0.05秒，这一帧0.2秒按两倍速推进0.4秒
+    assert_eq!(clock.advance(0.2), 4);
+    clock.speed_down();
+    clock.speed_down();
+    assert_eq!(clock.speed(), 0.5);
+    clock.speed_down();
+    assert_eq!(clock.speed(), 0.5);
+
+    clock.toggle_pause();
+    assert_eq!(clock.advance(10.0), 0);
+    clock.request_step();
+    assert_eq!(clock.advance(10.0), 1);
+    assert_eq!(clock.advance(10.0), 0);
+
+    clock.toggle_pause();
+    clock.request_step();
+    assert!(!clock.step_once);
+    assert_eq!(clock.advance(1000.0), clock.max_steps_per_frame);
+    assert_eq!(clock.advance(0.0), 0);
+}
+
+#[test]
+fn smoke_mass_balance_closes() {
+    let album = tower();
+    let mut para = parameters();
+    para.p = 1.0;
+    para.human_seed = 0.0;
+    let mut simulation = Simulation::new(&album, &para);
+    for _ in 0..600 {
+        simulation.step(0.1);
+    }
+    let balance = simulation.smoke.balance();
+    assert!(balance.produced > 0.0);
+    assert!(balance.vented > 0.0);
+    let error = balance.produced - balance.vented - balance.remaining;
+    assert!(
+        error.abs() <= 1e-3 * balance.produced,
+        "produced {} vented {} remaining {}",
+        balance.produced,
+        balance.vented,
+        balance.remaining
+    );
+}
+
+#[test]
+fn upper_storeys_evacuate_through_stairs() {
+    let album = tower();
+    let mut para = parameters();
+    para.p = 0.0;
+    let mut simulation = Simulation::new(&album, &para);
+    let upper = simulation
+        .crowd
+        .humans
+        .iter()
+        .filter(|human| human.storey.name != "s0")
+        .count();
+    let from_top = simulation
+        .crowd
+        .humans
+        .iter()
+        .filter(|human| human.storey.name == "s2")
+        .count();
+    assert!(upper > 0 && from_top > 0);
+
+    let mut changes = 0;
+    while !simulation.is_finished() && simulation.time < 300.0 {
+        changes += simulation
+            .step(0.1)
+            .iter()
+            .filter(|event| matches!(event, SimEvent::ChangeStorey(_)))
+            .count();
+    }
+    assert!(simulation.is_finished());
+    assert_eq!(simulation.dead_num(), 0);
+    assert_eq!(simulation.evacuated_num(), simulation.crowd.humans.len());
+    //楼梯格上下对齐，三层的人也一次走到首层，每个楼上的人换一次楼层
+    assert_eq!(changes, upper);
+    assert!(simulation
+        .crowd
+        .humans
+        .iter()
+        .all(|human| human.storey.name == "s0"));
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::{building, marker, parameters};
+use drawmymap2_lib::evacuation::things::smoke::BuildingSmoke;
+use drawmymap2_lib::evacuation::things::stairwell::BuildingStairwells;
+
+fn density(smoke: &BuildingSmoke, storey: &str) -> f32 {
+    smoke.maps[&marker(storey)].map[2][6].get_density()
+}
+
+//首层楼梯格的烟沿楼梯升到二楼再到三楼，升上去的量从下层扣掉，全楼烟雾总量不变
+#[test]
+fn stair_smoke_rises_and_is_conserved() {
+    let album = building(&["s0", "s1", "s2"], 5, 8, (2, 6));
+    let mut para = parameters();
+    para.stair_smoke_rate = 0.2;
+    let mut stairwells = BuildingStairwells::default();
+    stairwells.init_from_album(&album, &para);
+    assert_eq!(stairwells.links.len(), 2);
+    assert_eq!(stairwells.stairwells.len(), 1);
+    assert_eq!(stairwells.stairwells[0].tiles.len(), 3);
+    assert_eq!(stairwells.stairwells[0].name, "s0(2, 6)");
+
+    let mut smoke = BuildingSmoke::default();
+    smoke.init_from_album(&album);
+    smoke.maps.get_mut(&marker("s0")).unwrap().add((2, 6), 10.0);
+    stairwells.transfer(&mut smoke, 1.0);
+    let risen = 10.0 * (1.0 - (-0.2f32).exp());
+    assert!((density(&smoke, "s1") - risen).abs() < 1e-4);
+    assert!((density(&smoke, "s0") - (10.0 - risen)).abs() < 1e-4);
+    //各通道按这一步开始时的浓度计算，二楼这一步新升上来的烟下一步才继续上升
+    assert_eq!(density(&smoke, "s2"), 0.0);
+
+    for _ in 0..20 {
+        stairwells.transfer(&mut smoke, 1.0);
+    }
+    assert!(density(&smoke, "s2") > 0.0);
+    let balance = smoke.balance();
+    assert!((balance.produced - balance.vented - balance.remaining).abs() < 1e-4);
+}
+
+//梯间烟雾浓度第一次达到上限的时间记为被污染的时间，并按间隔记录梯间的烟雾
+#[test]
+fn stairwell_records_contamination() {
+    let album = building(&["s0", "s1"], 5, 8, (2, 6));
+    let mut para = parameters();
+    para.stair_smoke_limit = 1.0;
+    para.stairwell_sample_interval = 10.0;
+    let mut stairwells = BuildingStairwells::default();
+    stairwells.init_from_album(&album, &para);
+    let mut smoke = BuildingSmoke::default();
+    smoke.init_from_album(&album);
+
+    stairwells.record(&smoke, &para, 0.0);
+    smoke.maps.get_mut(&marker("s1")).unwrap().add((2, 6), 0.5);
+    stairwells.record(&smoke, &para, 5.0);
+    smoke.maps.get_mut(&marker("s1")).unwrap().add((2, 6), 0.5);
+    stairwells.record(&smoke, &para, 10.0);
+    stairwells.record(&smoke, &para, 15.0);
+
+    let outcome = &stairwells.outcomes()[0];
+    assert_eq!(outcome.storeys, vec!["s0".to_string(), "s1".to_string()]);
+    assert_eq!(outcome.contaminated_at, Some(10.0));
+    assert_eq!(outcome.peak, 1.0);
+    let times: Vec<f32> = outcome.samples.iter().map(|x| x.time).collect();
+    assert_eq!(times, vec![0.0, 10.0]);
+    assert_eq!(outcome.samples[1].mean, 0.5);
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{building, marker, parameters};
+use drawmymap2_lib::components::TileType;
+use drawmymap2_lib::evacuation::things::fire::{BuildingFire, Fire};
+use drawmymap2_lib::evacuation::things::smoke::BuildingSmoke;
+use drawmymap2_lib::evacuation::things::suppression::{BuildingSuppression, HeadState};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+//(3,3)是喷头，旁边一排可燃物，第6列是墙
+//喷头在着火后延迟一秒开始喷水，打湿半径内不隔墙的格，并把范围内的火扑灭
+#[test]
+fn sprinkler_wets_its_side_and_puts_out_fire() {
+    let mut album = building(&["s0"], 7, 12, (1, 10));
+    let s0 = marker("s0");
+    let map = album.maps.get_mut(&s0).unwrap();
+    map.tiles[3][3] = TileType::Hydrant;
+    for j in [2, 4] {
+        map.tiles[4][j] = TileType::Furniture;
+    }
+    for i in 1..6 {
+        map.tiles[i][6] = TileType::Wall;
+    }
+    let mut para = parameters();
+    para.suppression = true;
+    para.sprinkler_delay = 1.0;
+    para.sprinkler_radius = 4.0;
+    para.sprinkler_extinguish_rate = 1000.0;
+    let mut fire = BuildingFire::default();
+    fire.init_from_album(&album, &para);
+    let smoke = {
+        let mut smoke = BuildingSmoke::default();
+        smoke.init_from_album(&album);
+        smoke
+    };
+    let mut suppression = BuildingSuppression::default();
+    suppression.init_from_album(&album, &para);
+    assert_eq!(suppression.heads.len(), 1);
+
+    let mut rng = StdRng::seed_from_u64(1);
+    suppression.update(0.5, 0.5, &mut fire, &smoke, &para, &mut rng);
+    assert_eq!(suppression.heads[0].state, HeadState::Standby);
+
+    fire.maps.get_mut(&s0).unwrap().ignite((4, 4), &mut rng);
+    let mut time = 0.5;
+    while suppression.heads[0].state != HeadState::Active {
+        assert!(time < 10.0);
+        time += 0.5;
+        suppression.update(0.5, time, &mut fire, &smoke, &para, &mut rng);
+    }
+    assert_eq!(suppression.heads[0].activated_at, Some(2.0));
+    let storey_fire = &fire.maps[&s0];
+    assert!(storey_fire.wet[3][5]);
+    assert!(!storey_fire.wet[3][7]);
+    assert!(storey_fire.wet[4][2]);
+
+    suppression.update(0.5, time + 0.5, &mut fire, &smoke, &para, &mut rng);
+    assert!(matches!(fire.maps[&s0].map[4][4], Fire::NeverBurn));
+    assert!(fire.maps[&s0].burning.is_empty());
+    assert_eq!(suppression.extinguished_num(), 1);
+    assert_eq!(suppression.activated_num(), 1);
+
+    //打湿的可燃物不会被引燃，同样的火在没打湿时能引燃它
+    let storey_fire = fire.maps.get_mut(&s0).unwrap();
+    storey_fire.map[5][2] = Fire::Off;
+    storey_fire.ignite((5, 2), &mut rng);
+    if let Fire::On(timer) = &mut storey_fire.map[5][2] {
+        timer.burning_timer.tick(Duration::from_secs(30));
+    }
+    let mut dry = storey_fire.clone();
+    dry.wet[4][2] = false;
+    storey_fire.spread(1.0, &mut rng);
+    assert!(matches!(storey_fire.map[4][2], Fire::Off));
+    dry.spread(1.0, &mut rng);
+    assert!(matches!(dry.map[4][2], Fire::On(_)));
+}
+
+//不启用喷淋时没有喷头
+#[test]
+fn suppression_off_has_no_heads() {
+    let mut album = building(&["s0"], 7, 12, (1, 10));
+    album.maps.get_mut(&marker("s0")).unwrap().tiles[3][3] = TileType::Hydrant;
+    let mut para = parameters();
+    para.suppression = false;
+    let mut suppression = BuildingSuppression::default();
+    suppression.init_from_album(&album, &para);
+    assert!(suppression.heads.is_empty());
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::parameters;
+use drawmymap2_lib::evacuation::things::behaviour::Behaviour;
+use drawmymap2_lib::evacuation::things::heat::StoreyHeat;
+use drawmymap2_lib::evacuation::things::human::Human;
+use drawmymap2_lib::evacuation::things::smoke::StoreySmoke;
+use drawmymap2_lib::evacuation::things::toxicity::{Gases, ToxicityModel};
+
+//没有烟时空气正常，剂量为0
+#[test]
+fn clean_air_gives_no_dose() {
+    let para = parameters();
+    let gases = Gases::from_density(0.0, &para);
+    assert_eq!(gases.o2, 20.9);
+    assert_eq!(gases.dose_rate(), 0.0);
+    assert!(Gases::from_density(1.0, &para).dose_rate() > 0.0);
+}
+
+//在浓烟里累计剂量：达到fed_incapacitation时失去行动能力，血量不变，达到fed_death时死亡
+#[test]
+fn fed_incapacitates_then_kills() {
+    let mut para = parameters();
+    para.toxicity = ToxicityModel::Fed;
+    para.heat_damage = false;
+    let mut smoke = StoreySmoke::new(3, 3);
+    smoke.add((1, 1), 5.0);
+    let heat = StoreyHeat::new(3, 3, para.ambient_temperature);
+    let mut human = Human {
+        now_tile: (1, 1),
+        ..Default::default()
+    };
+
+    let mut time = 0.0;
+    while human.incapacitated_at.is_none() {
+        assert!(time < 3600.0);
+        time += 1.0;
+        human.fed_damage(&smoke, &heat, &para, 1.0, time);
+    }
+    assert!(human.total_dose() >= para.fed_incapacitation);
+    assert_eq!(human.incapacitated_at, Some(time));
+    assert!(!human.is_dead);
+    assert_eq!(human.hp, human.max_hp);
+    human.update_behaviour(5.0, &para);
+    assert_eq!(human.behaviour, Behaviour::Incapacitated);
+
+    while !human.is_dead {
+        assert!(time < 3600.0);
+        time += 1.0;
+        human.fed_damage(&smoke, &heat, &para, 1.0, time);
+    }
+    assert!(human.total_dose() >= para.fed_death);
+    assert_eq!(human.hp, 0.0);
+    assert_eq!(human.incapacitated_at.map(|x| x < time), Some(true));
+}